@@ -0,0 +1,574 @@
+//! Parsing of the `#[builder(...)]` attributes on the struct and its fields.
+//!
+//! `syn::Meta` only accepts literals on the right of `=`, so the attribute
+//! contents are parsed into `BuilderMeta` items instead, whose values are
+//! arbitrary expressions, e.g. `#[builder(default = Vec::new())]`.
+
+use crate::{Errors, Pattern};
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{
+    parenthesized, token, Attribute, Expr, ExprClosure, ExprLit, Field, Lit, LitStr, Pat, Path,
+    Token, Visibility,
+};
+
+/// Options given by `#[builder(...)]` on the struct itself.
+#[derive(Default)]
+pub(crate) struct StructOptions {
+    /// Track which required fields are set in the builder's type, see `typestate.rs`.
+    pub(crate) typestate: bool,
+    /// `setter(into)`: all setters accept `impl Into<T>`.
+    pub(crate) setter_into: bool,
+    /// `try_setter`: all fields get a `try_` setter accepting `impl TryInto<T>`.
+    pub(crate) try_setter: bool,
+    /// `pattern = "..."`: how setters and `build` take the builder.
+    pub(crate) pattern: Pattern,
+    /// `validate = "path::to::fn"`: checks the built value before `build` returns it.
+    pub(crate) validate: Option<Path>,
+    /// `name = "..."`: name of the builder type.
+    pub(crate) name: Option<Ident>,
+    /// `vis = "..."`: visibility of the builder and error types.
+    pub(crate) vis: Option<Visibility>,
+    /// `derive(...)`: traits derived for the builder.
+    pub(crate) derives: Vec<Ident>,
+    /// `setter(vis = "...")`: visibility of all setters.
+    pub(crate) setter_vis: Option<Visibility>,
+    /// `setter(prefix = "...")`: prepended to the names of all setters.
+    pub(crate) setter_prefix: Option<String>,
+    /// `doc = "..."`: documentation of the builder type.
+    pub(crate) doc: Option<LitStr>,
+    /// `no_std`: only refer to `core` and `alloc` in the generated code.
+    pub(crate) no_std: bool,
+    /// `serde`: the builder implements `Deserialize` and gets `merge`.
+    pub(crate) serde: bool,
+    /// `const`: the factory and setters are `const fn`, and the builder gets
+    /// `build_const`.
+    pub(crate) const_fn: bool,
+}
+
+/// Options given by `#[builder(...)]` on a field.
+#[derive(Default)]
+pub(crate) struct FieldOptions {
+    /// `name = "..."`: name of the builder slot and setter.
+    pub(crate) name: Option<Ident>,
+    /// One-at-a-time setter requested with `each = "..."`.
+    pub(crate) each: Option<Ident>,
+    /// `extend`: add an `extend_` setter accepting an iterator of items.
+    pub(crate) extend: bool,
+    /// Value used by `build` when the field was never set.
+    pub(crate) default: Option<FieldDefault>,
+    /// `skip` or `skip = expr`: the field has no setter and always gets this
+    /// value.
+    pub(crate) skip: Option<FieldDefault>,
+    /// `setter(into)`: the setter accepts `impl Into<T>`.
+    pub(crate) setter_into: bool,
+    /// `setter(strip_option = false)`: the setter of an `Option<T>` field
+    /// takes `Option<T>` instead of `T`.
+    pub(crate) strip_option: Option<bool>,
+    /// `setter(vis = "...")`: visibility of the field's setters.
+    pub(crate) setter_vis: Option<Visibility>,
+    /// `setter(name = "...")`: name of the setter, keeping the slot name.
+    pub(crate) setter_name: Option<Ident>,
+    /// `setter(transform = |...| ...)`: computes the value from the setter's
+    /// arguments.
+    pub(crate) transform: Option<ExprClosure>,
+    /// `doc = "..."`: documentation of the setters, instead of the field's.
+    pub(crate) doc: Option<LitStr>,
+    /// `try_setter`: add a `try_` setter accepting `impl TryInto<T>`.
+    pub(crate) try_setter: bool,
+    /// `sub_builder`: the setter returns the builder of the field's type. Holds
+    /// the key, for error messages.
+    pub(crate) sub_builder: Option<Ident>,
+    /// `env = "..."`: `fill_from_env` parses the field from this environment
+    /// variable.
+    pub(crate) env: Option<LitStr>,
+    /// `setter(async_fn)`: the setter takes a future resolving to the value.
+    /// Holds the key, for error messages.
+    pub(crate) async_fn: Option<Ident>,
+}
+
+pub(crate) enum FieldDefault {
+    /// `#[builder(default)]` or `#[builder(skip)]`, i.e. `Default::default()`.
+    /// Holds the key, for error messages.
+    Trait(Ident),
+    /// `#[builder(default = expr)]` or `#[builder(skip = expr)]`.
+    Expr(Box<Expr>),
+}
+
+/// One item inside `#[builder(...)]`.
+enum BuilderMeta {
+    /// `key`
+    Path(Ident),
+    /// `key = expr`
+    NameValue(Ident, Token![=], Box<Expr>),
+    /// `key(item, ...)`
+    List(Ident, token::Paren, Punctuated<BuilderMeta, Token![,]>),
+}
+
+impl BuilderMeta {
+    fn key(&self) -> &Ident {
+        match self {
+            BuilderMeta::Path(key)
+            | BuilderMeta::NameValue(key, ..)
+            | BuilderMeta::List(key, ..) => key,
+        }
+    }
+}
+
+impl Parse for BuilderMeta {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // Keys may be keywords, e.g. `#[builder(const)]`.
+        let key = Ident::parse_any(input)?;
+        if input.peek(Token![=]) {
+            Ok(BuilderMeta::NameValue(key, input.parse()?, input.parse()?))
+        } else if input.peek(token::Paren) {
+            let content;
+            let paren = parenthesized!(content in input);
+            let items = content.parse_terminated(BuilderMeta::parse)?;
+            Ok(BuilderMeta::List(key, paren, items))
+        } else {
+            Ok(BuilderMeta::Path(key))
+        }
+    }
+}
+
+impl ToTokens for BuilderMeta {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            BuilderMeta::Path(key) => key.to_tokens(tokens),
+            BuilderMeta::NameValue(key, eq, value) => {
+                key.to_tokens(tokens);
+                eq.to_tokens(tokens);
+                value.to_tokens(tokens);
+            }
+            BuilderMeta::List(key, paren, items) => {
+                key.to_tokens(tokens);
+                paren.surround(tokens, |tokens| items.to_tokens(tokens));
+            }
+        }
+    }
+}
+
+/// The items of every `#[builder(...)]` attribute in `attrs`, each paired with
+/// the attribute it came from.
+fn builder_metas<'a>(
+    attrs: &'a [Attribute],
+    errors: &mut Errors,
+) -> Vec<(&'a Attribute, BuilderMeta)> {
+    let mut metas = Vec::new();
+    for attr in attrs.iter().filter(|a| a.path.is_ident("builder")) {
+        match attr.parse_args_with(Punctuated::<BuilderMeta, Token![,]>::parse_terminated) {
+            Ok(items) => metas.extend(items.into_iter().map(|meta| (attr, meta))),
+            Err(err) => errors.push(err),
+        }
+    }
+    metas
+}
+
+pub(crate) fn parse_struct_attrs(attrs: &[Attribute]) -> syn::Result<StructOptions> {
+    let mut errors = Errors::default();
+    let mut options = StructOptions::default();
+    let mut pattern = None;
+    let mut const_key = None;
+
+    for (_, meta) in builder_metas(attrs, &mut errors) {
+        match &meta {
+            BuilderMeta::NameValue(key, _, value) if key == "pattern" => {
+                match parse_pattern(value) {
+                    Ok(value) => set_once(&mut pattern, value, &meta, &mut errors),
+                    Err(err) => errors.push(err),
+                }
+                if options.typestate {
+                    errors.push(syn::Error::new_spanned(
+                        &meta,
+                        "`pattern` cannot be combined with `typestate`",
+                    ));
+                }
+            }
+            BuilderMeta::Path(key) if key == "no_std" => {
+                set_flag(&mut options.no_std, &meta, &mut errors);
+            }
+            BuilderMeta::Path(key) if key == "const" => {
+                set_flag(&mut options.const_fn, &meta, &mut errors);
+                const_key = Some(key.clone());
+            }
+            BuilderMeta::Path(key) if key == "serde" => {
+                set_flag(&mut options.serde, &meta, &mut errors);
+                if options.typestate {
+                    errors.push(syn::Error::new_spanned(
+                        &meta,
+                        "`serde` cannot be combined with `typestate`",
+                    ));
+                }
+            }
+            BuilderMeta::Path(key) if key == "typestate" => {
+                set_flag(&mut options.typestate, &meta, &mut errors);
+                if pattern.is_some() {
+                    errors.push(syn::Error::new_spanned(
+                        &meta,
+                        "`typestate` cannot be combined with `pattern`",
+                    ));
+                }
+                if options.serde {
+                    errors.push(syn::Error::new_spanned(
+                        &meta,
+                        "`typestate` cannot be combined with `serde`",
+                    ));
+                }
+            }
+            BuilderMeta::Path(key) if key == "try_setter" => {
+                set_flag(&mut options.try_setter, &meta, &mut errors);
+            }
+            BuilderMeta::List(key, _, items) if key == "setter" => {
+                for item in items {
+                    match item {
+                        BuilderMeta::Path(key) if key == "into" => {
+                            set_flag(&mut options.setter_into, item, &mut errors);
+                        }
+                        BuilderMeta::NameValue(key, _, value) if key == "vis" => {
+                            match parse_vis(value) {
+                                Ok(vis) => {
+                                    set_once(&mut options.setter_vis, vis, item, &mut errors)
+                                }
+                                Err(err) => errors.push(err),
+                            }
+                        }
+                        BuilderMeta::NameValue(key, _, value) if key == "prefix" => {
+                            match parse_prefix(value) {
+                                Ok(prefix) => {
+                                    set_once(&mut options.setter_prefix, prefix, item, &mut errors)
+                                }
+                                Err(err) => errors.push(err),
+                            }
+                        }
+                        _ => errors.push(unknown_setter_option(item)),
+                    }
+                }
+            }
+            BuilderMeta::NameValue(key, _, value) if key == "name" => match parse_ident(value) {
+                Ok(name) => set_once(&mut options.name, name, &meta, &mut errors),
+                Err(err) => errors.push(err),
+            },
+            BuilderMeta::NameValue(key, _, value) if key == "vis" => match parse_vis(value) {
+                Ok(vis) => set_once(&mut options.vis, vis, &meta, &mut errors),
+                Err(err) => errors.push(err),
+            },
+            BuilderMeta::NameValue(key, _, value) if key == "doc" => match parse_lit_str(value) {
+                Ok(doc) => set_once(&mut options.doc, doc.clone(), &meta, &mut errors),
+                Err(err) => errors.push(err),
+            },
+            BuilderMeta::List(key, _, items) if key == "derive" => {
+                for item in items {
+                    match item {
+                        BuilderMeta::Path(derive) => options.derives.push(derive.clone()),
+                        _ => errors.push(syn::Error::new_spanned(item, "expected a trait name")),
+                    }
+                }
+            }
+            BuilderMeta::NameValue(key, _, value) if key == "validate" => {
+                match parse_lit_str(value).and_then(LitStr::parse) {
+                    Ok(path) => set_once(&mut options.validate, path, &meta, &mut errors),
+                    Err(err) => errors.push(err),
+                }
+            }
+            _ => errors.push(syn::Error::new_spanned(meta, "unknown builder attribute")),
+        }
+    }
+
+    // Only setters taking and returning the builder by value can be chained in
+    // a constant expression.
+    if let Some(key) = &const_key {
+        let conflicts = [
+            ("typestate", options.typestate),
+            ("validate", options.validate.is_some()),
+            ("setter(into)", options.setter_into),
+            ("try_setter", options.try_setter),
+        ];
+        for (conflict, _) in conflicts.iter().filter(|(_, set)| *set) {
+            let msg = format!("`const` cannot be combined with `{}`", conflict);
+            errors.push(syn::Error::new_spanned(key, msg));
+        }
+        if !matches!(pattern.get_or_insert(Pattern::Owned), Pattern::Owned) {
+            errors.push(syn::Error::new_spanned(
+                key,
+                "`const` requires the \"owned\" pattern",
+            ));
+        }
+    }
+
+    errors.finish()?;
+    options.pattern = pattern.unwrap_or_default();
+    Ok(options)
+}
+
+pub(crate) fn parse_field_attrs(field: &Field) -> syn::Result<FieldOptions> {
+    let mut errors = Errors::default();
+    let mut options = FieldOptions::default();
+
+    let metas = builder_metas(&field.attrs, &mut errors);
+    let meta_count = metas.len();
+    let mut skip_key = None;
+    for (attr, meta) in metas {
+        match &meta {
+            BuilderMeta::NameValue(key, _, value) if key == "name" => match parse_ident(value) {
+                Ok(name) => set_once(&mut options.name, name, &meta, &mut errors),
+                Err(err) => errors.push(err),
+            },
+            BuilderMeta::NameValue(key, _, value) if key == "each" => match parse_ident(value) {
+                Ok(each) => set_once(&mut options.each, each, &meta, &mut errors),
+                Err(err) => errors.push(err),
+            },
+            BuilderMeta::NameValue(key, _, value) if key == "doc" => match parse_lit_str(value) {
+                Ok(doc) => set_once(&mut options.doc, doc.clone(), &meta, &mut errors),
+                Err(err) => errors.push(err),
+            },
+            BuilderMeta::Path(key) if key == "extend" => {
+                set_flag(&mut options.extend, &meta, &mut errors);
+            }
+            BuilderMeta::Path(key) if key == "default" => {
+                let default = FieldDefault::Trait(key.clone());
+                set_once(&mut options.default, default, &meta, &mut errors);
+            }
+            BuilderMeta::NameValue(key, _, value) if key == "default" => {
+                let default = FieldDefault::Expr(value.clone());
+                set_once(&mut options.default, default, &meta, &mut errors);
+            }
+            BuilderMeta::Path(key) if key == "skip" => {
+                let skip = FieldDefault::Trait(key.clone());
+                set_once(&mut options.skip, skip, &meta, &mut errors);
+                skip_key = Some(key.clone());
+            }
+            BuilderMeta::NameValue(key, _, value) if key == "skip" => {
+                let skip = FieldDefault::Expr(value.clone());
+                set_once(&mut options.skip, skip, &meta, &mut errors);
+                skip_key = Some(key.clone());
+            }
+            BuilderMeta::Path(key) if key == "try_setter" => {
+                set_flag(&mut options.try_setter, &meta, &mut errors);
+            }
+            BuilderMeta::List(key, _, items) if key == "setter" => {
+                for item in items {
+                    match item {
+                        BuilderMeta::Path(key) if key == "into" => {
+                            set_flag(&mut options.setter_into, item, &mut errors);
+                        }
+                        BuilderMeta::NameValue(key, _, value) if key == "vis" => {
+                            match parse_vis(value) {
+                                Ok(vis) => {
+                                    set_once(&mut options.setter_vis, vis, item, &mut errors)
+                                }
+                                Err(err) => errors.push(err),
+                            }
+                        }
+                        BuilderMeta::NameValue(key, _, value) if key == "name" => {
+                            match parse_ident(value) {
+                                Ok(name) => {
+                                    set_once(&mut options.setter_name, name, item, &mut errors)
+                                }
+                                Err(err) => errors.push(err),
+                            }
+                        }
+                        BuilderMeta::NameValue(key, _, value) if key == "transform" => {
+                            match parse_transform(value) {
+                                Ok(transform) => {
+                                    set_once(&mut options.transform, transform, item, &mut errors)
+                                }
+                                Err(err) => errors.push(err),
+                            }
+                        }
+                        BuilderMeta::Path(key) if key == "async_fn" => {
+                            set_once(&mut options.async_fn, key.clone(), item, &mut errors);
+                        }
+                        BuilderMeta::NameValue(key, _, value) if key == "strip_option" => {
+                            match parse_lit_bool(value) {
+                                Ok(strip) => {
+                                    set_once(&mut options.strip_option, strip, item, &mut errors)
+                                }
+                                Err(err) => errors.push(err),
+                            }
+                        }
+                        _ => errors.push(unknown_setter_option(item)),
+                    }
+                }
+            }
+            BuilderMeta::Path(key) if key == "sub_builder" => {
+                set_once(&mut options.sub_builder, key.clone(), &meta, &mut errors);
+            }
+            BuilderMeta::NameValue(key, _, value) if key == "env" => match parse_lit_str(value) {
+                Ok(var) => set_once(&mut options.env, var.clone(), &meta, &mut errors),
+                Err(err) => errors.push(err),
+            },
+            _ => {
+                let path = &attr.path;
+                let tokens = &attr.tokens;
+                errors.push(syn::Error::new_spanned(
+                    quote!(#path #tokens),
+                    "expected `builder(each = \"...\")`",
+                ));
+            }
+        }
+    }
+
+    if let Some(transform) = options.transform.as_ref().filter(|_| options.setter_into) {
+        errors.push(syn::Error::new_spanned(
+            transform,
+            "`setter(transform = ...)` cannot be combined with `setter(into)`",
+        ));
+    }
+
+    // A skipped field has no setter for the other options to apply to.
+    if let Some(key) = skip_key.filter(|_| meta_count > 1) {
+        errors.push(syn::Error::new_spanned(
+            key,
+            "`skip` cannot be combined with other builder options",
+        ));
+    }
+
+    if let Some(key) = &options.sub_builder {
+        let conflicts = [
+            ("each", options.each.is_some()),
+            ("extend", options.extend),
+            ("default", options.default.is_some()),
+            ("setter(into)", options.setter_into),
+            ("setter(transform)", options.transform.is_some()),
+            ("try_setter", options.try_setter),
+        ];
+        for (conflict, _) in conflicts.iter().filter(|(_, set)| *set) {
+            let msg = format!("`sub_builder` cannot be combined with `{}`", conflict);
+            errors.push(syn::Error::new_spanned(key, msg));
+        }
+    }
+
+    if let Some(key) = &options.async_fn {
+        let conflicts = [
+            ("each", options.each.is_some()),
+            ("extend", options.extend),
+            ("sub_builder", options.sub_builder.is_some()),
+            ("setter(into)", options.setter_into),
+            ("setter(transform)", options.transform.is_some()),
+            ("try_setter", options.try_setter),
+            ("env", options.env.is_some()),
+        ];
+        for (conflict, _) in conflicts.iter().filter(|(_, set)| *set) {
+            let msg = format!("`setter(async_fn)` cannot be combined with `{}`", conflict);
+            errors.push(syn::Error::new_spanned(key, msg));
+        }
+    }
+
+    // The variable holds a single value, parsed into the field's type.
+    if let Some(var) = &options.env {
+        let conflicts = [
+            ("each", options.each.is_some()),
+            ("extend", options.extend),
+            ("sub_builder", options.sub_builder.is_some()),
+        ];
+        for (conflict, _) in conflicts.iter().filter(|(_, set)| *set) {
+            let msg = format!("`env` cannot be combined with `{}`", conflict);
+            errors.push(syn::Error::new_spanned(var, msg));
+        }
+    }
+
+    errors.finish()?;
+    Ok(options)
+}
+
+/// Store `value` in `slot`, reporting an error if the option was given before.
+fn set_once<T>(slot: &mut Option<T>, value: T, meta: &BuilderMeta, errors: &mut Errors) {
+    if slot.is_some() {
+        errors.push(duplicate(meta));
+    }
+    *slot = Some(value);
+}
+
+fn set_flag(flag: &mut bool, meta: &BuilderMeta, errors: &mut Errors) {
+    if *flag {
+        errors.push(duplicate(meta));
+    }
+    *flag = true;
+}
+
+fn duplicate(meta: &BuilderMeta) -> syn::Error {
+    let msg = format!("duplicate `{}` attribute", meta.key());
+    syn::Error::new_spanned(meta, msg)
+}
+
+fn unknown_setter_option(meta: &BuilderMeta) -> syn::Error {
+    syn::Error::new_spanned(meta, "unknown `setter(...)` option")
+}
+
+fn parse_lit_str(value: &Expr) -> syn::Result<&LitStr> {
+    match value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit), ..
+        }) => Ok(lit),
+        _ => Err(syn::Error::new_spanned(value, "expected a string literal")),
+    }
+}
+
+fn parse_lit_bool(value: &Expr) -> syn::Result<bool> {
+    match value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Bool(lit),
+            ..
+        }) => Ok(lit.value),
+        _ => Err(syn::Error::new_spanned(value, "expected `true` or `false`")),
+    }
+}
+
+fn parse_pattern(value: &Expr) -> syn::Result<Pattern> {
+    let lit = parse_lit_str(value)?;
+    match lit.value().as_str() {
+        "owned" => Ok(Pattern::Owned),
+        "mutable" => Ok(Pattern::Mutable),
+        "immutable" => Ok(Pattern::Immutable),
+        _ => Err(syn::Error::new_spanned(
+            lit,
+            "expected \"owned\", \"mutable\" or \"immutable\"",
+        )),
+    }
+}
+
+/// A visibility given as a string literal, e.g. `vis = "pub(crate)"`. An
+/// empty string means private.
+fn parse_vis(value: &Expr) -> syn::Result<Visibility> {
+    parse_lit_str(value)?.parse()
+}
+
+/// A setter prefix, which together with a field name must form an identifier.
+fn parse_prefix(value: &Expr) -> syn::Result<String> {
+    let lit = parse_lit_str(value)?;
+    let prefix = lit.value();
+    match syn::parse_str::<Ident>(&format!("{}field", prefix)) {
+        Ok(_) => Ok(prefix),
+        Err(_) => Err(syn::Error::new_spanned(
+            lit,
+            "expected an identifier prefix",
+        )),
+    }
+}
+
+/// A closure whose parameters all have a type, e.g. `|s: &str| s.len()`.
+fn parse_transform(value: &Expr) -> syn::Result<ExprClosure> {
+    let Expr::Closure(closure) = value else {
+        return Err(syn::Error::new_spanned(value, "expected a closure"));
+    };
+    match closure
+        .inputs
+        .iter()
+        .find(|input| !matches!(input, Pat::Type(_)))
+    {
+        Some(input) => Err(syn::Error::new_spanned(
+            input,
+            "closure parameters need a type, e.g. `|s: &str|`",
+        )),
+        None => Ok(closure.clone()),
+    }
+}
+
+/// An identifier given as a string literal, e.g. `each = "arg"`.
+fn parse_ident(value: &Expr) -> syn::Result<Ident> {
+    parse_lit_str(value)?.parse()
+}
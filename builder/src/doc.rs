@@ -0,0 +1,180 @@
+//! Doc comments for the generated items, so that the builder is documented in
+//! the rustdoc of the crate deriving it.
+
+use crate::{InternalField, Target};
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::ext::IdentExt;
+
+/// `#[doc = "..."]` attributes for `text`, one per line.
+pub(crate) fn doc_attrs(text: &str) -> TokenStream {
+    let lines = text.lines().map(|line| {
+        if line.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", line)
+        }
+    });
+    quote! { #(#[doc = #lines])* }
+}
+
+/// The builder struct, unless overridden by `#[builder(doc = "...")]`.
+pub(crate) fn builder(target: &Target, fields: &[InternalField]) -> TokenStream {
+    if let Some(doc) = &target.options.doc {
+        return doc_attrs(&doc.value());
+    }
+    let mut text = format!(
+        "Builder for [`{}`], created by [`{}::{}`].\n\n",
+        target.value_name, target.struct_name, target.factory_name,
+    );
+    match required_list(fields) {
+        Some(required) => text += &format!("Required fields: {}.", required),
+        None => text += "All fields are optional.",
+    }
+    doc_attrs(&text)
+}
+
+/// The associated function creating an empty builder.
+pub(crate) fn factory(target: &Target) -> TokenStream {
+    doc_attrs(&format!(
+        "Creates a [`{}`] with no fields set.",
+        target.builder_name,
+    ))
+}
+
+pub(crate) fn into_builder(target: &Target) -> TokenStream {
+    doc_attrs(&format!(
+        "Creates a [`{}`] with every field set from `self`.",
+        target.builder_name,
+    ))
+}
+
+pub(crate) fn to_builder(target: &Target) -> TokenStream {
+    doc_attrs(&format!(
+        "Creates a [`{}`] with every field set to a clone of the one in `self`.",
+        target.builder_name,
+    ))
+}
+
+pub(crate) fn missing_fields(target: &Target, build: &Ident) -> TokenStream {
+    doc_attrs(&format!(
+        "Names of the required fields that are not set yet, for which \
+         [`{}`](Self::{}) would fail with [`{}::MissingFields`].",
+        build, build, target.error_name,
+    ))
+}
+
+pub(crate) fn merge(target: &Target) -> TokenStream {
+    doc_attrs(&format!(
+        "Sets every field that is set in `other`, replacing the value in `self`.\n\n\
+         Fields that are not set in `other` keep their value. Collections are \
+         replaced rather than extended, and nested builders are merged field by \
+         field. Combined with the [`Deserialize`](::serde::Deserialize) impl of \
+         [`{}`], this layers values from config files, the environment and code \
+         before [`{}::build`] reports what is still missing.",
+        target.builder_name, target.builder_name,
+    ))
+}
+
+pub(crate) fn from_env(target: &Target) -> TokenStream {
+    doc_attrs(&format!(
+        "Creates a [`{}`] with the fields read from the environment by \
+         [`fill_from_env`](Self::fill_from_env) set.",
+        target.builder_name,
+    ))
+}
+
+/// `fill_from_env`, listing the variables it reads.
+pub(crate) fn fill_from_env(target: &Target, fields: &[&InternalField]) -> TokenStream {
+    let vars: Vec<_> = fields
+        .iter()
+        .filter_map(|f| f.env.as_ref())
+        .map(|var| format!("`{}`", var.value()))
+        .collect();
+    doc_attrs(&format!(
+        "Sets the fields read from the environment variables {}, parsing \
+         their values with `FromStr`. Variables that are not set leave their \
+         field unchanged.\n\n\
+         # Errors\n\n\
+         Fails with [`{}::InvalidEnv`] if a variable is not valid unicode or \
+         its value cannot be parsed.",
+        vars.join(", "),
+        target.error_name,
+    ))
+}
+
+pub(crate) fn error(target: &Target, build: &Ident) -> TokenStream {
+    doc_attrs(&format!(
+        "Error returned by [`{}::{}`].",
+        target.builder_name, build,
+    ))
+}
+
+/// The `build` method, listing how it can fail.
+pub(crate) fn build(target: &Target, fields: &[InternalField]) -> TokenStream {
+    let mut text = format!(
+        "Builds a [`{}`] from the values set so far.",
+        target.value_name
+    );
+    let required = required_list(fields);
+
+    let mut failures = Vec::new();
+    match &required {
+        Some(required) if target.options.typestate => {
+            text += &format!(
+                "\n\nOnly available once the required fields {} are set.",
+                required
+            );
+        }
+        Some(required) => {
+            failures.push(format!(
+                "one of the required fields {} was not set",
+                required
+            ));
+        }
+        None => {}
+    }
+    if fields.iter().any(|f| f.async_init.is_some()) {
+        text += "\n\nAwaits the futures given to the `setter(async_fn)` setters, \
+                 one after the other in field order.";
+        failures.push("one of these futures fails".to_owned());
+    }
+    if fields.iter().any(|f| f.sub_builder.is_some()) {
+        failures.push("building a `sub_builder` field fails".to_owned());
+    }
+    if target.options.validate.is_some() {
+        failures.push("the validator rejects the value".to_owned());
+    }
+    if !failures.is_empty() {
+        text += &format!("\n\n# Errors\n\nFails if {}.", failures.join(", or if "));
+    }
+    doc_attrs(&text)
+}
+
+/// `build_const` of a `#[builder(const)]` builder.
+pub(crate) fn build_const(target: &Target, fields: &[InternalField]) -> TokenStream {
+    let mut text = format!(
+        "Builds a [`{}`] from the values set so far, in a `const fn` so that \
+         it can initialize a `const` or `static`.",
+        target.value_name,
+    );
+    if let Some(required) = required_list(fields) {
+        text += &format!(
+            "\n\n# Panics\n\n\
+             Panics if one of the required fields {} was not set, which fails \
+             the compilation when evaluated in a constant.",
+            required,
+        );
+    }
+    doc_attrs(&text)
+}
+
+/// The required fields as code spans, e.g. "`host`, `port`".
+fn required_list(fields: &[InternalField]) -> Option<String> {
+    let names: Vec<_> = fields
+        .iter()
+        .filter(|f| f.is_mandatory())
+        .map(|f| format!("`{}`", f.name.unraw()))
+        .collect();
+    (!names.is_empty()).then(|| names.join(", "))
+}
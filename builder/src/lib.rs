@@ -1,42 +1,328 @@
+use attr::{FieldDefault, StructOptions};
 use proc_macro::TokenStream;
 use proc_macro2::Ident;
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
 use syn::{
-    parse_macro_input, Data, DeriveInput, Fields, FieldsNamed, GenericArgument, PathArguments, Type,
+    parse_macro_input, parse_quote, Data, DeriveInput, ExprClosure, Fields, GenericArgument,
+    Generics, Index, LitStr, Member, Pat, PatType, PathArguments, PathSegment, Type, TypePath,
+    Visibility,
 };
 
+mod attr;
+mod doc;
+mod typestate;
+
 #[proc_macro_derive(Builder, attributes(builder))]
 pub fn derive(input: proc_macro::TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
-    let struct_name = input.ident;
 
-    let Data::Struct(ds) = input.data else {
-        panic!("Only structs are supported.");
-    };
-    let Fields::Named(fields) = ds.fields else {
-        panic!("Only named fields are supported.");
+    let result = match expand(&input) {
+        Ok(result) => result,
+        Err(err) => err.to_compile_error(),
     };
-
-    let result = generate(&struct_name, &fields);
     //eprintln!("{}", pretty_print(&result));
     TokenStream::from(result)
 }
 
-fn generate(struct_name: &Ident, fields: &FieldsNamed) -> proc_macro2::TokenStream {
-    let builder_name = format_ident!("{}Builder", struct_name);
-    let builder_factory = make_builder_factory(&builder_name, fields, struct_name);
-    let builder = make_builder(struct_name, &builder_name, fields);
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let struct_name = &input.ident;
+    let mut errors = Errors::default();
+    let options = attr::parse_struct_attrs(&input.attrs).unwrap_or_else(|err| {
+        errors.push(err);
+        StructOptions::default()
+    });
+
+    // A struct gets one builder, an enum gets one builder per variant.
+    let mut targets = Vec::new();
+    match &input.data {
+        Data::Struct(ds) => {
+            let builder_name = match &options.name {
+                Some(name) => name.clone(),
+                None => format_ident!("{}Builder", struct_name),
+            };
+            let target = Target {
+                struct_name,
+                error_name: format_ident!("{}Error", builder_name),
+                builder_name,
+                factory_name: format_ident!("builder"),
+                value_name: struct_name.to_string(),
+                constructor: quote!(#struct_name),
+                round_trip: true,
+                vis: &input.vis,
+                generics: &input.generics,
+                options: &options,
+            };
+            targets.push((target, &ds.fields));
+        }
+        Data::Enum(de) => {
+            if let Some(name) = &options.name {
+                errors.push(syn::Error::new_spanned(
+                    name,
+                    "`name` is not supported on enums, whose builders are named after each variant",
+                ));
+            }
+            for variant in &de.variants {
+                if let Some(attr) = variant.attrs.iter().find(|a| a.path.is_ident("builder")) {
+                    errors.push(syn::Error::new_spanned(
+                        attr,
+                        "builder attributes are not supported on enum variants",
+                    ));
+                }
+                let variant_name = &variant.ident;
+                let target = Target {
+                    struct_name,
+                    builder_name: format_ident!("{}{}Builder", struct_name, variant_name),
+                    error_name: format_ident!("{}{}BuilderError", struct_name, variant_name),
+                    factory_name: format_ident!("{}_builder", snake_case(variant_name)),
+                    value_name: format!("{}::{}", struct_name, variant_name),
+                    constructor: quote!(#struct_name::#variant_name),
+                    round_trip: false,
+                    vis: &input.vis,
+                    generics: &input.generics,
+                    options: &options,
+                };
+                targets.push((target, &variant.fields));
+            }
+        }
+        Data::Union(du) => {
+            return Err(syn::Error::new_spanned(
+                du.union_token,
+                "Builder can only be derived for structs and enums",
+            ))
+        }
+    }
+
+    let mut result = proc_macro2::TokenStream::new();
+    for (target, fields) in &targets {
+        match transform_fields(fields, target) {
+            Ok((fields, skipped)) => result.extend(generate(target, &fields, &skipped)),
+            Err(err) => errors.push(err),
+        }
+    }
+    errors.finish()?;
+    Ok(result)
+}
+
+/// The struct, or enum variant, a builder is generated for.
+struct Target<'a> {
+    /// Name of the struct or enum.
+    struct_name: &'a Ident,
+    builder_name: Ident,
+    /// Error type returned by `build`.
+    error_name: Ident,
+    /// Associated function of `struct_name` returning a new builder.
+    factory_name: Ident,
+    /// The struct or variant in docs, e.g. `Shape::Circle`.
+    value_name: String,
+    /// Path used in the struct expression creating the value, e.g. `Shape::Circle`.
+    constructor: proc_macro2::TokenStream,
+    /// Whether the value gets `into_builder` and `to_builder`. Not for enum
+    /// variants, as the value may hold a different variant.
+    round_trip: bool,
+    /// Visibility of the struct, inherited by `build` and, unless overridden
+    /// by `setter(vis = "...")`, by the setters.
+    vis: &'a Visibility,
+    generics: &'a Generics,
+    options: &'a StructOptions,
+}
+
+impl Target<'_> {
+    /// A builder may not mention all generic parameters in its fields, e.g. for
+    /// an enum variant or when the typestate builder replaces field types by
+    /// state parameters, so it holds a marker mentioning them.
+    fn marker_field(&self) -> Option<proc_macro2::TokenStream> {
+        if self.generics.params.is_empty() {
+            return None;
+        }
+        let struct_name = self.struct_name;
+        let (_, ty_generics, _) = self.generics.split_for_impl();
+        Some(quote! { __marker: ::core::marker::PhantomData<fn() -> #struct_name #ty_generics> })
+    }
+
+    /// Visibility of the builder, its error type and the functions returning the
+    /// builder, from `#[builder(vis = "...")]`.
+    fn builder_vis(&self) -> proc_macro2::TokenStream {
+        match &self.options.vis {
+            Some(vis) => quote!(#vis),
+            None => quote!(pub),
+        }
+    }
+
+    /// `#[derive(...)]` on the builder: `Clone` if the pattern needs it,
+    /// `Deserialize` for `#[builder(serde)]`, plus the traits from
+    /// `#[builder(derive(...))]`.
+    fn builder_derives(&self) -> Option<proc_macro2::TokenStream> {
+        let pattern_clone = !self.options.typestate && self.options.pattern.is_clone();
+        let mut derives = Vec::new();
+        if pattern_clone {
+            derives.push(quote!(::core::clone::Clone));
+        }
+        if self.options.serde {
+            derives.push(quote!(::serde::Deserialize));
+        }
+        derives.extend(
+            self.options
+                .derives
+                .iter()
+                .filter(|derive| !(pattern_clone && *derive == "Clone"))
+                .map(|derive| quote!(#derive)),
+        );
+        (!derives.is_empty()).then(|| quote!(#[derive(#(#derives),*)]))
+    }
+
+    /// Whether `#[builder(derive(...))]` lists `name`, which replaces the
+    /// impl generated for that trait.
+    fn derives_trait(&self, name: &str) -> bool {
+        self.options.derives.iter().any(|derive| derive == name)
+    }
+
+    /// `Default` for the builder, creating it like the factory does.
+    fn make_default_impl(
+        &self,
+        builder_ty: &proc_macro2::TokenStream,
+    ) -> Option<proc_macro2::TokenStream> {
+        if self.derives_trait("Default") {
+            return None;
+        }
+        let Target {
+            struct_name,
+            factory_name,
+            generics,
+            ..
+        } = self;
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        Some(quote! {
+            impl #impl_generics ::core::default::Default for #builder_ty #where_clause {
+                fn default() -> Self {
+                    <#struct_name #ty_generics>::#factory_name()
+                }
+            }
+        })
+    }
+
+    /// Root of the paths to `Vec` and `Box`, which is `alloc` for
+    /// `#[builder(no_std)]`.
+    fn alloc_crate(&self) -> proc_macro2::TokenStream {
+        if self.options.no_std {
+            quote!(::alloc)
+        } else {
+            quote!(::std)
+        }
+    }
+
+    /// Initializer for the field declared by `marker_field`.
+    fn marker_init(&self) -> Option<proc_macro2::TokenStream> {
+        self.marker_field()
+            .map(|_| quote! { __marker: ::core::marker::PhantomData })
+    }
+}
+
+fn generate(
+    target: &Target,
+    fields: &[InternalField],
+    skipped: &[SkippedField],
+) -> proc_macro2::TokenStream {
+    if target.options.typestate {
+        return typestate::generate(target, fields, skipped);
+    }
+
+    let builder_factory = make_builder_factory(target, fields);
+    let builder = make_builder(target, fields, skipped);
+    let (_, ty_generics, _) = target.generics.split_for_impl();
+    let builder_name = &target.builder_name;
+    let round_trip = make_round_trip(
+        target,
+        fields,
+        quote!(#builder_name #ty_generics),
+        &|field, value| field.init_slot(value),
+    );
+    let error = make_error_type(target, fields);
 
     quote! {
         #builder_factory
         #builder
+        #round_trip
+        #error
     }
 }
 
 struct InternalField<'a> {
-    name: &'a Ident,
+    /// Name of the builder slot and setter; `_0`, `_1`, ... for tuple fields
+    /// unless renamed with `#[builder(name = "...")]`.
+    name: Ident,
+    /// The field in the struct expression creating the value.
+    member: Member,
+    /// Name of the setter, `name` unless changed by `setter(name = "...")` or
+    /// `setter(prefix = "...")`.
+    setter: Ident,
+    /// `setter(transform = |...| ...)`: the setter takes the closure's
+    /// parameters and stores what it returns.
+    transform: Option<ExprClosure>,
     ty: &'a Type,
     inner_ty: Option<&'a Type>,
+    /// One-at-a-time setter requested with `#[builder(each = "...")]`.
+    each: Option<Each<'a>>,
+    /// Generate an `extend_` setter accepting an iterator of items.
+    extend: bool,
+    /// Value of the field if its setter was never called, from `#[builder(default)]`.
+    default: Option<proc_macro2::TokenStream>,
+    /// Setters accept `impl Into<T>` instead of `T`.
+    setter_into: bool,
+    /// Generate a `try_` setter accepting `impl TryInto<T>`.
+    try_setter: bool,
+    /// Visibility of the setters.
+    setter_vis: Visibility,
+    /// Doc attributes of the setters, copied from the field unless given by
+    /// `#[builder(doc = "...")]`.
+    doc: proc_macro2::TokenStream,
+    /// Builder of the field's type for `#[builder(sub_builder)]`, which is
+    /// stored in the slot and returned by the setter.
+    sub_builder: Option<Type>,
+    /// Environment variable read by `fill_from_env`, from `#[builder(env = "...")]`.
+    env: Option<LitStr>,
+    /// The slot holds a future resolving to the value, from
+    /// `#[builder(setter(async_fn))]`.
+    async_init: Option<AsyncInit>,
+}
+
+/// Slot of a `setter(async_fn)` field, awaited by `build_async`.
+struct AsyncInit {
+    /// `Pin<Box<dyn Future<Output = Result<T, Box<dyn Error>>> + Send>>`.
+    slot: Type,
+    /// Path to `Box`, from `std` or `alloc`.
+    boxed: proc_macro2::TokenStream,
+}
+
+/// A field with `#[builder(skip)]`, which has no builder slot or setter.
+struct SkippedField {
+    member: Member,
+    /// Value of the field in the built struct.
+    value: proc_macro2::TokenStream,
+}
+
+impl ToTokens for SkippedField {
+    /// The field initializer in the struct expression.
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let SkippedField { member, value } = self;
+        tokens.extend(quote! { #member: #value });
+    }
+}
+
+struct Each<'a> {
+    setter: Ident,
+    collection: Collection<'a>,
+}
+
+/// The kind of collection a field with a one-at-a-time setter holds.
+enum Collection<'a> {
+    /// `Vec<T>`, a set or another well-known collection of `T`.
+    Elements(&'a Type),
+    /// `HashMap<K, V>` or `BTreeMap<K, V>`; the setter takes a key and a value.
+    Map(&'a Type, &'a Type),
+    /// Any other `Default + Extend<T>` collection; the setter is generic over `T`.
+    Other,
 }
 
 impl<'a> InternalField<'a> {
@@ -47,41 +333,489 @@ impl<'a> InternalField<'a> {
             None => self.ty,
         }
     }
+
+    /// Type of the builder slot, inside an `Option`.
+    fn slot_type(&self) -> &Type {
+        match (&self.sub_builder, &self.async_init) {
+            (Some(builder_ty), _) => builder_ty,
+            (None, Some(async_init)) => &async_init.slot,
+            (None, None) => self.get_core_type(),
+        }
+    }
+
+    /// Parameter type of a setter for a value of type `ty`, and the expression
+    /// converting the parameter `arg` to `ty`.
+    fn setter_arg(
+        &self,
+        arg: &Ident,
+        ty: &Type,
+    ) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+        if self.setter_into {
+            (
+                quote! { impl ::core::convert::Into<#ty> },
+                quote! { ::core::convert::Into::into(#arg) },
+            )
+        } else {
+            (quote! { #ty }, quote! { #arg })
+        }
+    }
+
+    /// Parameters of the setter storing a value of type `ty`, and the
+    /// expression computing the value from them.
+    fn setter_params(&self, ty: &Type) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+        let Some(transform) = &self.transform else {
+            let name = &self.name;
+            let (arg_ty, value) = self.setter_arg(name, ty);
+            return (quote! { #name: #arg_ty }, value);
+        };
+
+        let (params, args): (Vec<_>, Vec<_>) = transform
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(i, input)| {
+                let Pat::Type(PatType { pat, ty, .. }) = input else {
+                    unreachable!("closure parameters are checked in parse_field_attrs");
+                };
+                let arg = match &**pat {
+                    Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => {
+                        pat.ident.clone()
+                    }
+                    _ => format_ident!("__arg{}", i),
+                };
+                (quote! { #arg: #ty }, arg)
+            })
+            .unzip();
+        (quote! { #(#params),* }, quote! { (#transform)(#(#args),*) })
+    }
+
+    /// Name of the `try_` setter.
+    fn try_setter_name(&self) -> Ident {
+        format_ident!("try_{}", self.setter.unraw())
+    }
+
+    /// Turn the `Option` stored in the builder slot into the field's value.
+    /// Mandatory fields are expected to have been checked beforehand.
+    fn unwrap_slot(&self, slot: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        match &self.default {
+            // Only evaluated if the field was not set.
+            Some(default) => {
+                let value = match self.inner_ty {
+                    Some(_) => quote! { ::core::option::Option::Some(value) },
+                    None => quote! { value },
+                };
+                quote! {
+                    match #slot {
+                        ::core::option::Option::Some(value) => #value,
+                        ::core::option::Option::None => #default,
+                    }
+                }
+            }
+            None if self.inner_ty.is_some() => slot,
+            None if self.each.is_some() || self.extend => quote! { #slot.unwrap_or_default() },
+            None => quote! { #slot.unwrap() },
+        }
+    }
+
+    /// Builder slot holding the field's `value`, as used by `into_builder`.
+    fn init_slot(&self, value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        if self.sub_builder.is_some() {
+            return quote! { ::core::option::Option::Some(#value.into_builder()) };
+        }
+        // An already resolved future.
+        if let Some(AsyncInit { slot, boxed }) = &self.async_init {
+            let ready = quote! {
+                #boxed::pin(::core::future::ready(::core::result::Result::Ok(value))) as #slot
+            };
+            return match self.inner_ty {
+                Some(_) => quote! { ::core::option::Option::map(#value, |value| #ready) },
+                None => quote! { ::core::option::Option::Some({ let value = #value; #ready }) },
+            };
+        }
+        match self.inner_ty {
+            Some(_) => value,
+            None => quote! { ::core::option::Option::Some(#value) },
+        }
+    }
+
+    /// Whether `build` must fail if this field was never set.
+    fn is_mandatory(&self) -> bool {
+        self.inner_ty.is_none()
+            && self.each.is_none()
+            && !self.extend
+            && self.default.is_none()
+            && self.sub_builder.is_none()
+    }
 }
 
-fn transform_fields<'a>(fields: &'a FieldsNamed) -> Vec<InternalField<'a>> {
-    fields
-        .named
-        .pairs()
-        .map(|pair| {
-            let field = pair.value();
-            let ty = &field.ty;
-            let inner_ty = find_inner_type(ty);
-            let name = field.ident.as_ref().unwrap();
-
-            InternalField { name, ty, inner_ty }
-        })
-        .collect()
+/// How setters and `build` take the builder, from `#[builder(pattern = "...")]`.
+#[derive(Clone, Copy, Default)]
+enum Pattern {
+    /// Setters take `&mut self` and `build` moves the values out of the
+    /// builder, leaving it empty. Does not require any field to be `Clone`.
+    #[default]
+    Draining,
+    /// Setters take `&mut self`, `build` takes `&self` and clones the values.
+    Mutable,
+    /// Setters and `build` take `self` by value.
+    Owned,
+    /// Setters take `&self` and return a modified clone, `build` takes `&self`
+    /// and clones the values.
+    Immutable,
 }
 
-fn make_builder_factory(
-    builder_name: &Ident,
-    struct_fields: &FieldsNamed,
-    struct_name: &Ident,
-) -> proc_macro2::TokenStream {
-    let builder_initial_fields: Vec<_> = transform_fields(struct_fields)
-        .into_iter()
+impl Pattern {
+    fn setter_receiver(self) -> proc_macro2::TokenStream {
+        match self {
+            Pattern::Draining | Pattern::Mutable => quote!(&mut self),
+            Pattern::Owned => quote!(mut self),
+            Pattern::Immutable => quote!(&self),
+        }
+    }
+
+    fn setter_ret(self) -> proc_macro2::TokenStream {
+        match self {
+            Pattern::Draining | Pattern::Mutable => quote!(&mut Self),
+            Pattern::Owned | Pattern::Immutable => quote!(Self),
+        }
+    }
+
+    /// The builder a setter modifies and returns.
+    fn setter_this(self) -> proc_macro2::TokenStream {
+        match self {
+            Pattern::Immutable => quote!(__builder),
+            _ => quote!(self),
+        }
+    }
+
+    /// Statements at the start of a setter, declaring `setter_this`.
+    fn setter_prelude(self) -> Option<proc_macro2::TokenStream> {
+        match self {
+            Pattern::Immutable => {
+                Some(quote! { let mut __builder = ::core::clone::Clone::clone(self); })
+            }
+            _ => None,
+        }
+    }
+
+    fn build_receiver(self) -> proc_macro2::TokenStream {
+        match self {
+            Pattern::Draining => quote!(&mut self),
+            Pattern::Mutable | Pattern::Immutable => quote!(&self),
+            Pattern::Owned => quote!(self),
+        }
+    }
+
+    /// Expression getting the `Option` out of the builder slot in `build`.
+    fn build_slot(self, name: &Ident) -> proc_macro2::TokenStream {
+        match self {
+            Pattern::Draining => quote! { ::core::mem::take(&mut self.#name) },
+            Pattern::Mutable | Pattern::Immutable => {
+                quote! { ::core::clone::Clone::clone(&self.#name) }
+            }
+            Pattern::Owned => quote! { self.#name },
+        }
+    }
+
+    /// Whether the builder derives `Clone`, so it can be used as a template.
+    fn is_clone(self) -> bool {
+        matches!(self, Pattern::Mutable | Pattern::Immutable)
+    }
+}
+
+/// Collects several errors so they can be reported in one go.
+#[derive(Default)]
+struct Errors(Option<syn::Error>);
+
+impl Errors {
+    fn push(&mut self, err: syn::Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(err),
+            None => self.0 = Some(err),
+        }
+    }
+
+    fn finish(self) -> syn::Result<()> {
+        match self.0 {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+fn transform_fields<'a>(
+    fields: &'a Fields,
+    target: &Target,
+) -> syn::Result<(Vec<InternalField<'a>>, Vec<SkippedField>)> {
+    let struct_options = target.options;
+    let mut errors = Errors::default();
+    let mut result = Vec::new();
+    let mut skipped = Vec::new();
+
+    for (index, field) in fields.iter().enumerate() {
+        let ty = &field.ty;
+
+        let options = match attr::parse_field_attrs(field) {
+            Ok(options) => options,
+            Err(err) => {
+                errors.push(err);
+                continue;
+            }
+        };
+
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index {
+                index: index as u32,
+                span: ty.span(),
+            }),
+        };
+        // `Default::default()` is not a `const fn`, so `build_const` cannot
+        // call it.
+        if struct_options.const_fn {
+            let trait_defaults = [&options.default, &options.skip];
+            for default in trait_defaults.into_iter().flatten() {
+                if let FieldDefault::Trait(key) = default {
+                    let msg = format!(
+                        "`{}` without an expression is not supported by `const` builders, \
+                         use `{} = expr`",
+                        key, key,
+                    );
+                    errors.push(syn::Error::new_spanned(key, msg));
+                }
+            }
+        }
+        if let Some(skip) = options.skip {
+            let value = default_value(ty, skip);
+            skipped.push(SkippedField { member, value });
+            continue;
+        }
+        let name = match (options.name, &field.ident) {
+            (Some(name), _) => name,
+            (None, Some(ident)) => ident.clone(),
+            (None, None) => format_ident!("_{}", index, span = ty.span()),
+        };
+
+        if struct_options.const_fn {
+            let conflicts = [
+                ("each", options.each.is_some()),
+                ("extend", options.extend),
+                ("sub_builder", options.sub_builder.is_some()),
+                ("setter(into)", options.setter_into),
+                ("setter(transform)", options.transform.is_some()),
+                ("try_setter", options.try_setter),
+            ];
+            let mut conflicting = conflicts.iter().filter(|(_, set)| *set).peekable();
+            if conflicting.peek().is_some() {
+                for (conflict, _) in conflicting {
+                    let msg = format!("`{}` is not supported by `const` builders", conflict);
+                    errors.push(syn::Error::new_spanned(field, msg));
+                }
+                continue;
+            }
+        }
+
+        let each = match options.each {
+            Some(setter) => match collection_kind(ty) {
+                Some(collection) => Some(Each { setter, collection }),
+                None => {
+                    errors.push(syn::Error::new_spanned(
+                        ty,
+                        "`builder(each = \"...\")` requires a collection field, \
+                         e.g. `Vec<T>` or `HashMap<K, V>`",
+                    ));
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        let default = options.default.map(|default| default_value(ty, default));
+
+        let (inner_ty, default) = match (option_inner_type(ty), options.strip_option) {
+            // The setter takes the `Option<T>` itself, so it is treated like a
+            // field of any other type that defaults to `None`.
+            (Some(_), Some(false)) => {
+                let none = quote!(::core::option::Option::None);
+                (None, default.or(Some(none)))
+            }
+            (None, Some(_)) => {
+                errors.push(syn::Error::new_spanned(
+                    ty,
+                    "`setter(strip_option = ...)` requires a field of type `Option<T>`",
+                ));
+                continue;
+            }
+            (inner_ty, _) => (inner_ty, default),
+        };
+
+        let sub_builder = match &options.sub_builder {
+            Some(key) => {
+                if struct_options.typestate {
+                    errors.push(syn::Error::new_spanned(
+                        key,
+                        "`sub_builder` cannot be combined with `typestate`",
+                    ));
+                    continue;
+                }
+                if matches!(struct_options.pattern, Pattern::Owned | Pattern::Immutable) {
+                    errors.push(syn::Error::new_spanned(
+                        key,
+                        "`sub_builder` requires setters taking `&mut self`, \
+                         i.e. the default or \"mutable\" pattern",
+                    ));
+                    continue;
+                }
+                match sub_builder_type(ty).filter(|_| option_inner_type(ty).is_none()) {
+                    Some(builder_ty) => Some(builder_ty),
+                    None => {
+                        errors.push(syn::Error::new_spanned(
+                            ty,
+                            "`sub_builder` requires a field whose type derives `Builder`",
+                        ));
+                        continue;
+                    }
+                }
+            }
+            None => None,
+        };
+
+        if let Some(key) = &options.async_fn {
+            let conflict = if struct_options.typestate {
+                Some("`setter(async_fn)` cannot be combined with `typestate`")
+            } else if struct_options.const_fn {
+                Some("`setter(async_fn)` is not supported by `const` builders")
+            } else if struct_options.serde {
+                Some("`setter(async_fn)` cannot be combined with `serde`")
+            } else if struct_options.pattern.is_clone() {
+                Some(
+                    "`setter(async_fn)` requires a builder that is not cloned, \
+                     i.e. the default or \"owned\" pattern",
+                )
+            } else {
+                None
+            };
+            if let Some(msg) = conflict {
+                errors.push(syn::Error::new_spanned(key, msg));
+                continue;
+            }
+        }
+
+        if let Some(var) = &options.env {
+            let conflict = if struct_options.typestate {
+                Some("`env` cannot be combined with `typestate`")
+            } else if struct_options.no_std {
+                Some("`env` needs the standard library, which `no_std` excludes")
+            } else {
+                None
+            };
+            if let Some(msg) = conflict {
+                errors.push(syn::Error::new_spanned(var, msg));
+                continue;
+            }
+        }
+
+        let setter = match (options.setter_name, &struct_options.setter_prefix) {
+            (Some(setter), _) => setter,
+            (None, Some(prefix)) => format_ident!("{}{}", prefix, name.unraw(), span = name.span()),
+            (None, None) => name.clone(),
+        };
+
+        let doc = match &options.doc {
+            Some(doc) => doc::doc_attrs(&doc.value()),
+            None => {
+                let docs = field.attrs.iter().filter(|a| a.path.is_ident("doc"));
+                quote! { #(#docs)* }
+            }
+        };
+
+        let async_init = options.async_fn.as_ref().map(|_| {
+            let boxed = target.alloc_crate();
+            let boxed = quote!(#boxed::boxed::Box);
+            let value_ty = inner_ty.unwrap_or(ty);
+            let slot = parse_quote! {
+                ::core::pin::Pin<#boxed<
+                    dyn ::core::future::Future<
+                        Output = ::core::result::Result<
+                            #value_ty,
+                            #boxed<dyn ::core::error::Error + ::core::marker::Send + ::core::marker::Sync>,
+                        >,
+                    > + ::core::marker::Send,
+                >>
+            };
+            AsyncInit { slot, boxed }
+        });
+
+        // The setter of a sub-builder field takes no value.
+        let has_setter = sub_builder.is_none();
+        result.push(InternalField {
+            name,
+            member,
+            setter,
+            transform: options.transform,
+            ty,
+            inner_ty,
+            each,
+            extend: options.extend,
+            default,
+            setter_into: (options.setter_into || struct_options.setter_into) && has_setter,
+            try_setter: (options.try_setter || struct_options.try_setter) && has_setter,
+            setter_vis: options
+                .setter_vis
+                .or_else(|| struct_options.setter_vis.clone())
+                .unwrap_or_else(|| target.vis.clone()),
+            doc,
+            sub_builder,
+            env: options.env,
+            async_init,
+        });
+    }
+
+    errors.finish()?;
+    Ok((result, skipped))
+}
+
+/// The expression for `#[builder(default)]` or `#[builder(skip)]` on a field
+/// of type `ty`.
+fn default_value(ty: &Type, default: FieldDefault) -> proc_macro2::TokenStream {
+    match default {
+        FieldDefault::Trait(_) => {
+            quote_spanned!(ty.span()=> <#ty as ::core::default::Default>::default())
+        }
+        FieldDefault::Expr(expr) => quote!(#expr),
+    }
+}
+
+fn make_builder_factory(target: &Target, fields: &[InternalField]) -> proc_macro2::TokenStream {
+    let Target {
+        struct_name,
+        builder_name,
+        generics,
+        ..
+    } = target;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let factory_name = &target.factory_name;
+    let marker = target.marker_init();
+    let doc = doc::factory(target);
+    let constness = target.options.const_fn.then(|| quote!(const));
+    let builder_vis = target.builder_vis();
+
+    let builder_initial_fields: Vec<_> = fields
+        .iter()
         .map(|f| {
-            let name = f.name;
+            let name = &f.name;
             quote! {
-                #name: None
+                #name: ::core::option::Option::None
             }
         })
+        .chain(marker)
         .collect();
 
     let builder_factory = quote! {
-        impl #struct_name {
-            pub fn builder() -> #builder_name {
+        impl #impl_generics #struct_name #ty_generics #where_clause {
+            #doc
+            #builder_vis #constness fn #factory_name() -> #builder_name #ty_generics {
                 #builder_name {
                     #(#builder_initial_fields),*
                 }
@@ -91,114 +825,938 @@ fn make_builder_factory(
     builder_factory
 }
 
+/// `into_builder` and `to_builder`, returning a builder of type `builder_ty`
+/// with every slot set from an existing value.
+fn make_round_trip(
+    target: &Target,
+    fields: &[InternalField],
+    builder_ty: proc_macro2::TokenStream,
+    init_slot: &dyn Fn(&InternalField, proc_macro2::TokenStream) -> proc_macro2::TokenStream,
+) -> Option<proc_macro2::TokenStream> {
+    if !target.round_trip {
+        return None;
+    }
+    let Target {
+        struct_name,
+        builder_name,
+        generics,
+        ..
+    } = target;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let marker = target.marker_init();
+
+    let names: Vec<_> = fields.iter().map(|f| &f.name).collect();
+    let moved = fields.iter().map(|f| {
+        let member = &f.member;
+        init_slot(f, quote!(self.#member))
+    });
+    let cloned = fields.iter().map(|f| {
+        let member = &f.member;
+        init_slot(f, quote!(::core::clone::Clone::clone(&self.#member)))
+    });
+
+    // Without `for<'__a>` a bound like `Socket: Clone` on a non-generic field
+    // is rejected when it does not hold; this way `to_builder` is merely
+    // unavailable.
+    let predicates = where_clause.iter().flat_map(|w| &w.predicates);
+    let clone_bounds = fields.iter().map(|f| {
+        let ty = f.ty;
+        quote! { for<'__a> #ty: ::core::clone::Clone }
+    });
+    // The value of a `setter(async_fn)` field is stored as a ready future,
+    // which has to be `Send + 'static` like the ones passed to the setter.
+    let async_bounds: Vec<_> = fields
+        .iter()
+        .filter(|f| f.async_init.is_some())
+        .map(|f| {
+            let ty = f.ty;
+            quote! { for<'__a> #ty: ::core::marker::Send + 'static }
+        })
+        .collect();
+
+    let builder_vis = target.builder_vis();
+    let into_doc = doc::into_builder(target);
+    let to_doc = doc::to_builder(target);
+    Some(quote! {
+        impl #impl_generics #struct_name #ty_generics #where_clause {
+            #into_doc
+            #builder_vis fn into_builder(self) -> #builder_ty
+            where
+                #(#async_bounds,)*
+            {
+                #builder_name {
+                    #(#names: #moved,)*
+                    #marker
+                }
+            }
+
+            #to_doc
+            #builder_vis fn to_builder(&self) -> #builder_ty
+            where
+                #(#predicates,)*
+                #(#clone_bounds,)*
+                #(#async_bounds,)*
+            {
+                #builder_name {
+                    #(#names: #cloned,)*
+                    #marker
+                }
+            }
+        }
+    })
+}
+
 fn make_builder(
-    struct_name: &Ident,
-    builder_name: &Ident,
-    fields: &FieldsNamed,
+    target: &Target,
+    fields: &[InternalField],
+    skipped: &[SkippedField],
 ) -> proc_macro2::TokenStream {
-    // Map each "field: Type" to "field: Option<Type>"
-    let builder_fields: Vec<_> = transform_fields(fields)
-        .into_iter()
+    let Target {
+        builder_name,
+        generics,
+        ..
+    } = target;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // Map each "field: Type" to "field: Option<Type>". With serde, fields
+    // missing from the input stay unset.
+    let serde = target.options.serde;
+    let slot_attr = serde.then(|| quote!(#[serde(default)]));
+    let marker_attr = serde.then(|| quote!(#[serde(skip)]));
+    let builder_fields: Vec<_> = fields
+        .iter()
         .map(|field| {
-            let name = field.name;
-            let ty = field.get_core_type();
+            let name = &field.name;
+            let ty = field.slot_type();
 
             quote! {
-                #name: Option<#ty>
+                #slot_attr
+                #name: ::core::option::Option<#ty>
             }
         })
+        .chain(
+            target
+                .marker_field()
+                .map(|marker| quote!(#marker_attr #marker)),
+        )
         .collect();
 
-    let setters = make_builder_setters(&fields);
-    let build_method = make_build_method(struct_name, fields);
+    let pattern = target.options.pattern;
+    let setters = if target.options.const_fn {
+        fields.iter().map(make_const_setter).collect()
+    } else {
+        make_builder_setters(fields, pattern)
+    };
+    let build_const = target
+        .options
+        .const_fn
+        .then(|| make_build_const(target, fields, skipped));
+    let build_method = make_build_method(target, fields, skipped);
+    let merge = serde.then(|| make_merge(target, fields));
+    let env = make_env_methods(target, fields);
+    let missing_fields = make_missing_fields(target, fields);
+    let default_impl = target.make_default_impl(&quote!(#builder_name #ty_generics));
+    let debug_impl = make_debug_impl(target, fields);
+    let derives = target.builder_derives();
+    let vis = target.builder_vis();
+    let doc = doc::builder(target, fields);
     quote! {
-        pub struct #builder_name {
+        #doc
+        #derives
+        #vis struct #builder_name #generics #where_clause {
             #(#builder_fields),*
         }
 
-        impl #builder_name {
+        impl #impl_generics #builder_name #ty_generics #where_clause {
             #(#setters)*
 
+            #missing_fields
+
+            #merge
+
+            #env
+
             #build_method
+
+            #build_const
         }
+
+        #default_impl
+        #debug_impl
     }
 }
 
-fn make_builder_setters(struct_fields: &FieldsNamed) -> Vec<proc_macro2::TokenStream> {
-    transform_fields(struct_fields)
-        .into_iter()
-        .map(|field| {
-            let name = field.name;
-            let ty = field.get_core_type();
+/// `missing_fields`, listing the required fields that are not set yet.
+fn make_missing_fields(target: &Target, fields: &[InternalField]) -> proc_macro2::TokenStream {
+    let vis = target.vis;
+    let alloc = target.alloc_crate();
+    let doc = doc::missing_fields(target, &build_method_name(fields));
+    let mandatory_field_names = fields.iter().filter(|f| f.is_mandatory()).map(|f| &f.name);
+    quote! {
+        #doc
+        #vis fn missing_fields(&self) -> #alloc::vec::Vec<&'static str> {
+            let mut missing = #alloc::vec::Vec::new();
+            #(
+            if self.#mandatory_field_names.is_none() {
+                missing.push(::core::stringify!(#mandatory_field_names));
+            }
+            )*
+            missing
+        }
+    }
+}
+
+/// `Debug` for the builder, showing for each field whether it is `set`,
+/// `pending` (required but not set) or will get its `default`. The values are
+/// not shown, so the field types need not implement `Debug`; deriving `Debug`
+/// with `#[builder(derive(Debug))]` shows them instead.
+fn make_debug_impl(target: &Target, fields: &[InternalField]) -> Option<proc_macro2::TokenStream> {
+    if target.derives_trait("Debug") {
+        return None;
+    }
+    let Target {
+        builder_name,
+        generics,
+        ..
+    } = target;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let debug_fields = fields.iter().map(|field| {
+        let name = &field.name;
+        let unset = if field.is_mandatory() {
+            "pending"
+        } else {
+            "default"
+        };
+        quote! {
+            .field(
+                ::core::stringify!(#name),
+                &::core::format_args!(
+                    "{}",
+                    if self.#name.is_some() { "set" } else { #unset },
+                ),
+            )
+        }
+    });
+    Some(quote! {
+        impl #impl_generics ::core::fmt::Debug for #builder_name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                f.debug_struct(::core::stringify!(#builder_name))
+                    #(#debug_fields)*
+                    .finish()
+            }
+        }
+    })
+}
 
+/// `merge` for `#[builder(serde)]`, combining builders filled from different
+/// sources.
+fn make_merge(target: &Target, fields: &[InternalField]) -> proc_macro2::TokenStream {
+    let vis = target.vis;
+    let doc = doc::merge(target);
+    let merge_fields = fields.iter().map(|field| {
+        let name = &field.name;
+        if field.sub_builder.is_some() {
             quote! {
-                fn #name(&mut self, #name: #ty) -> &mut Self {
-                    self.#name = Some(#name);
-                    self
+                if let ::core::option::Option::Some(other) = other.#name {
+                    match &mut self.#name {
+                        ::core::option::Option::Some(this) => this.merge(other),
+                        ::core::option::Option::None => {
+                            self.#name = ::core::option::Option::Some(other);
+                        }
+                    }
                 }
             }
-        })
+        } else {
+            quote! {
+                if other.#name.is_some() {
+                    self.#name = other.#name;
+                }
+            }
+        }
+    });
+    quote! {
+        #doc
+        #vis fn merge(&mut self, other: Self) {
+            #(#merge_fields)*
+        }
+    }
+}
+
+/// `from_env` and `fill_from_env`, if any field has `#[builder(env = "...")]`.
+fn make_env_methods(target: &Target, fields: &[InternalField]) -> Option<proc_macro2::TokenStream> {
+    let env_fields: Vec<_> = fields.iter().filter(|f| f.env.is_some()).collect();
+    if env_fields.is_empty() {
+        return None;
+    }
+
+    let Target {
+        struct_name,
+        error_name,
+        factory_name,
+        vis,
+        generics,
+        ..
+    } = target;
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let boxed_error = quote! {
+        ::std::boxed::Box<dyn ::core::error::Error + ::core::marker::Send + ::core::marker::Sync>
+    };
+    let reads = env_fields.iter().map(|field| {
+        let name = &field.name;
+        let var = &field.env;
+        // With `setter(strip_option = false)` the slot holds the whole
+        // `Option<T>`, which is set to `Some` of the parsed `T`.
+        let (ty, value) = match (field.inner_ty, option_inner_type(field.ty)) {
+            (None, Some(inner_ty)) => (inner_ty, quote!(::core::option::Option::Some(value))),
+            _ => (field.get_core_type(), quote!(value)),
+        };
+        let invalid = quote! {
+            #error_name::InvalidEnv {
+                var: #var,
+                field: ::core::stringify!(#name),
+                source: ::core::convert::Into::<#boxed_error>::into(err),
+            }
+        };
+        quote! {
+            match ::std::env::var(#var) {
+                ::core::result::Result::Ok(value) => {
+                    match <#ty as ::core::str::FromStr>::from_str(&value) {
+                        ::core::result::Result::Ok(value) => {
+                            self.#name = ::core::option::Option::Some(#value);
+                        }
+                        ::core::result::Result::Err(err) => {
+                            return ::core::result::Result::Err(#invalid);
+                        }
+                    }
+                }
+                ::core::result::Result::Err(::std::env::VarError::NotPresent) => {}
+                ::core::result::Result::Err(err) => return ::core::result::Result::Err(#invalid),
+            }
+        }
+    });
+
+    let from_env_doc = doc::from_env(target);
+    let fill_from_env_doc = doc::fill_from_env(target, &env_fields);
+    Some(quote! {
+        #from_env_doc
+        #vis fn from_env() -> ::core::result::Result<Self, #error_name> {
+            let mut builder = <#struct_name #ty_generics>::#factory_name();
+            builder.fill_from_env()?;
+            ::core::result::Result::Ok(builder)
+        }
+
+        #fill_from_env_doc
+        #vis fn fill_from_env(&mut self) -> ::core::result::Result<(), #error_name> {
+            #(#reads)*
+            ::core::result::Result::Ok(())
+        }
+    })
+}
+
+fn make_builder_setters(
+    fields: &[InternalField],
+    pattern: Pattern,
+) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .iter()
+        .map(|field| make_field_setters(field, pattern))
         .collect()
 }
 
-fn make_build_method(struct_name: &Ident, struct_fields: &FieldsNamed) -> proc_macro2::TokenStream {
-    let mandatory_field_names: Vec<_> = transform_fields(struct_fields)
-        .into_iter()
-        .filter(|f| f.inner_ty.is_none())
-        .map(|f| f.name)
-        .collect();
+/// Setter of a `#[builder(const)]` builder. A `const fn` cannot run the
+/// destructor of the value it replaces, so that value is leaked instead.
+fn make_const_setter(field: &InternalField) -> proc_macro2::TokenStream {
+    let name = &field.name;
+    let setter_name = &field.setter;
+    let vis = &field.setter_vis;
+    let doc = &field.doc;
+    let (params, value) = field.setter_params(field.get_core_type());
+    quote! {
+        #doc
+        #vis const fn #setter_name(mut self, #params) -> Self {
+            ::core::mem::forget(::core::mem::replace(
+                &mut self.#name,
+                ::core::option::Option::Some(#value),
+            ));
+            self
+        }
+    }
+}
 
-    let optional_field_names: Vec<_> = transform_fields(struct_fields)
-        .into_iter()
-        .filter(|f| f.inner_ty.is_some())
-        .map(|f| f.name)
-        .collect();
+/// `build_const` for `#[builder(const)]`, which panics instead of returning an
+/// error so that a missing field fails the constant evaluation.
+///
+/// The values are taken out of the slots with `Option::take` and the emptied
+/// builder is forgotten, as a `const fn` cannot drop a value whose type may
+/// have a destructor.
+fn make_build_const(
+    target: &Target,
+    fields: &[InternalField],
+    skipped: &[SkippedField],
+) -> proc_macro2::TokenStream {
+    let Target {
+        struct_name,
+        constructor,
+        vis,
+        generics,
+        ..
+    } = target;
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let doc = doc::build_const(target, fields);
 
+    let field_members = fields.iter().map(|f| &f.member);
+    let field_values = fields.iter().map(|field| {
+        let name = &field.name;
+        let value = match field.inner_ty {
+            Some(_) => quote! { self.#name.take() },
+            None => quote! { self.#name.take().unwrap() },
+        };
+        match &field.default {
+            Some(default) => quote! {
+                if self.#name.is_some() { #value } else { #default }
+            },
+            None if field.inner_ty.is_some() => value,
+            None => {
+                let msg = format!("missing required field `{}`", name.unraw());
+                quote! { self.#name.take().expect(#msg) }
+            }
+        }
+    });
     quote! {
-        fn build (&mut self) -> Result<#struct_name, Box<dyn std::error::Error>> {
-            #(
-            if self.#mandatory_field_names.is_none() {
-                let msg = format!("{} has no value.", stringify!(#mandatory_field_names));
-                return Err(msg.into());
+        #doc
+        #vis const fn build_const(mut self) -> #struct_name #ty_generics {
+            let value = #constructor {
+                #(#field_members: #field_values,)*
+                #(#skipped,)*
+            };
+            ::core::mem::forget(self);
+            value
+        }
+    }
+}
+
+/// Setters storing into the field's builder slot.
+fn make_field_setters(field: &InternalField, pattern: Pattern) -> proc_macro2::TokenStream {
+    let name = &field.name;
+    let setter_name = &field.setter;
+    let vis = &field.setter_vis;
+    let doc = &field.doc;
+    if let Some(builder_ty) = &field.sub_builder {
+        let ty = field.ty;
+        return quote! {
+            #doc
+            #vis fn #setter_name(&mut self) -> &mut #builder_ty {
+                self.#name.get_or_insert_with(<#ty>::builder)
             }
-            )*
+        };
+    }
+    let ty = field.get_core_type();
+    let receiver = pattern.setter_receiver();
+    let ret = pattern.setter_ret();
+    let this = pattern.setter_this();
+    let prelude = pattern.setter_prelude();
 
-            Ok(#struct_name {
-                #(#mandatory_field_names: std::mem::take(&mut self.#mandatory_field_names).unwrap(),)*
-                #(#optional_field_names: std::mem::take(&mut self.#optional_field_names),)*
-            })
+    if let Some(AsyncInit { boxed, .. }) = &field.async_init {
+        let boxed_error = quote! {
+            #boxed<dyn ::core::error::Error + ::core::marker::Send + ::core::marker::Sync>
+        };
+        return quote! {
+            #doc
+            #vis fn #setter_name<__F, __E>(#receiver, #name: __F) -> #ret
+            where
+                __F: ::core::future::Future<Output = ::core::result::Result<#ty, __E>>
+                    + ::core::marker::Send
+                    + 'static,
+                __E: ::core::convert::Into<#boxed_error>,
+            {
+                #this.#name = ::core::option::Option::Some(#boxed::pin(async move {
+                    match #name.await {
+                        ::core::result::Result::Ok(value) => ::core::result::Result::Ok(value),
+                        ::core::result::Result::Err(err) => {
+                            ::core::result::Result::Err(<__E as ::core::convert::Into<#boxed_error>>::into(err))
+                        }
+                    }
+                }));
+                #this
+            }
+        };
+    }
+
+    let (params, value) = field.setter_params(ty);
+    let all_at_once = quote! {
+        #doc
+        #vis fn #setter_name(#receiver, #params) -> #ret {
+            #prelude
+            #this.#name = ::core::option::Option::Some(#value);
+            #this
+        }
+    };
+
+    let try_setter = field.try_setter.then(|| {
+        let try_name = field.try_setter_name();
+        quote! {
+            #doc
+            #vis fn #try_name<__T>(
+                #receiver,
+                #name: __T,
+            ) -> ::core::result::Result<#ret, <__T as ::core::convert::TryInto<#ty>>::Error>
+            where
+                __T: ::core::convert::TryInto<#ty>,
+            {
+                let #name = ::core::convert::TryInto::try_into(#name)?;
+                #prelude
+                #this.#name = ::core::option::Option::Some(#name);
+                ::core::result::Result::Ok(#this)
+            }
+        }
+    });
+
+    let collection = quote! {
+        #this.#name.get_or_insert_with(::core::default::Default::default)
+    };
+    let extend = field.extend.then(|| {
+        let extend_name = format_ident!("extend_{}", name.unraw());
+        quote! {
+            #doc
+            #vis fn #extend_name<__I>(#receiver, #name: __I) -> #ret
+            where
+                __I: ::core::iter::IntoIterator,
+                #ty: ::core::iter::Extend<<__I as ::core::iter::IntoIterator>::Item>,
+            {
+                #prelude
+                ::core::iter::Extend::extend(#collection, #name);
+                #this
+            }
+        }
+    });
 
+    let Some(Each {
+        setter,
+        collection: kind,
+    }) = &field.each
+    else {
+        return quote! {
+            #all_at_once
+            #try_setter
+            #extend
+        };
+    };
+    let one_at_a_time = match kind {
+        Collection::Elements(elem_ty) => {
+            let (arg_ty, value) = field.setter_arg(setter, elem_ty);
+            quote! {
+                #doc
+                #vis fn #setter(#receiver, #setter: #arg_ty) -> #ret {
+                    #prelude
+                    ::core::iter::Extend::extend(#collection, ::core::iter::once(#value));
+                    #this
+                }
+            }
+        }
+        Collection::Map(key_ty, value_ty) => {
+            let (key_arg_ty, key) = field.setter_arg(&format_ident!("key"), key_ty);
+            let (value_arg_ty, value) = field.setter_arg(&format_ident!("value"), value_ty);
+            quote! {
+                #doc
+                #vis fn #setter(#receiver, key: #key_arg_ty, value: #value_arg_ty) -> #ret {
+                    #prelude
+                    ::core::iter::Extend::extend(#collection, ::core::iter::once((#key, #value)));
+                    #this
+                }
+            }
+        }
+        Collection::Other => quote! {
+            #doc
+            #vis fn #setter<__T>(#receiver, #setter: __T) -> #ret
+            where
+                #ty: ::core::iter::Extend<__T>,
+            {
+                #prelude
+                ::core::iter::Extend::extend(#collection, ::core::iter::once(#setter));
+                #this
+            }
+        },
+    };
+
+    // Both setters would have the same name; only keep the one-at-a-time one.
+    if setter == setter_name {
+        quote! {
+            #one_at_a_time
+            #try_setter
+            #extend
+        }
+    } else {
+        quote! {
+            #all_at_once
+            #one_at_a_time
+            #try_setter
+            #extend
+        }
+    }
+}
+
+fn make_build_method(
+    target: &Target,
+    fields: &[InternalField],
+    skipped: &[SkippedField],
+) -> proc_macro2::TokenStream {
+    let Target {
+        struct_name,
+        error_name,
+        constructor,
+        vis,
+        generics,
+        ..
+    } = target;
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let pattern = target.options.pattern;
+    let receiver = pattern.build_receiver();
+    let doc = doc::build(target, fields);
+
+    let field_members = fields.iter().map(|f| &f.member);
+    let field_values = fields.iter().map(|f| {
+        let slot = pattern.build_slot(&f.name);
+        match (&f.sub_builder, &f.async_init) {
+            (Some(_), _) => make_sub_build(f, slot, error_name),
+            (None, Some(_)) => make_async_init(f, slot, error_name),
+            (None, None) => f.unwrap_slot(slot),
+        }
+    });
+    let build_name = build_method_name(fields);
+    let asyncness = fields
+        .iter()
+        .any(|f| f.async_init.is_some())
+        .then(|| quote!(async));
+    let ret = make_validated_return(
+        target,
+        quote! {
+            #constructor {
+                #(#field_members: #field_values,)*
+                #(#skipped,)*
+            }
+        },
+    );
+
+    quote! {
+        #doc
+        #vis #asyncness fn #build_name(#receiver) -> ::core::result::Result<#struct_name #ty_generics, #error_name> {
+            let missing = self.missing_fields();
+            if !missing.is_empty() {
+                return ::core::result::Result::Err(#error_name::MissingFields(missing));
+            }
+
+            #ret
+        }
+    }
+}
+
+/// `build_async` instead of `build` if a field is set by a future.
+fn build_method_name(fields: &[InternalField]) -> Ident {
+    if fields.iter().any(|f| f.async_init.is_some()) {
+        format_ident!("build_async")
+    } else {
+        format_ident!("build")
+    }
+}
+
+/// Value of a `setter(async_fn)` field, awaiting the future in `slot`.
+fn make_async_init(
+    field: &InternalField,
+    slot: proc_macro2::TokenStream,
+    error_name: &Ident,
+) -> proc_macro2::TokenStream {
+    let name = &field.name;
+    let value = field.unwrap_slot(quote!(value));
+    quote! {{
+        let value = match #slot {
+            ::core::option::Option::Some(init) => match init.await {
+                ::core::result::Result::Ok(value) => ::core::option::Option::Some(value),
+                ::core::result::Result::Err(source) => {
+                    return ::core::result::Result::Err(#error_name::InitFailed {
+                        field: ::core::stringify!(#name),
+                        source,
+                    });
+                }
+            },
+            ::core::option::Option::None => ::core::option::Option::None,
+        };
+        #value
+    }}
+}
+
+/// Value of a `sub_builder` field, built by the builder in `slot`, or by an
+/// empty one if the setter was never called.
+fn make_sub_build(
+    field: &InternalField,
+    slot: proc_macro2::TokenStream,
+    error_name: &Ident,
+) -> proc_macro2::TokenStream {
+    let name = &field.name;
+    let ty = field.ty;
+    quote! {
+        match ::core::option::Option::unwrap_or_else(#slot, <#ty>::builder).build() {
+            ::core::result::Result::Ok(value) => value,
+            ::core::result::Result::Err(err) => {
+                return ::core::result::Result::Err(#error_name::SubBuilder {
+                    field: ::core::stringify!(#name),
+                    source: ::core::convert::Into::into(err),
+                });
+            }
+        }
+    }
+}
+
+/// Return `value` from `build`, running the validator first if there is one.
+fn make_validated_return(
+    target: &Target,
+    value: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let Some(validate) = &target.options.validate else {
+        return quote! { ::core::result::Result::Ok(#value) };
+    };
+    let error_name = &target.error_name;
+
+    quote! {
+        let value = #value;
+        match #validate(&value) {
+            ::core::result::Result::Ok(()) => ::core::result::Result::Ok(value),
+            ::core::result::Result::Err(err) => ::core::result::Result::Err(
+                #error_name::ValidationFailed(::core::convert::Into::into(err)),
+            ),
+        }
+    }
+}
+
+/// The error type returned by `build`, named after the builder.
+fn make_error_type(target: &Target, fields: &[InternalField]) -> proc_macro2::TokenStream {
+    let error_name = &target.error_name;
+    let vis = target.builder_vis();
+    let doc = doc::error(target, &build_method_name(fields));
+    let alloc = target.alloc_crate();
+    let boxed_error = quote! {
+        #alloc::boxed::Box<dyn ::core::error::Error + ::core::marker::Send + ::core::marker::Sync>
+    };
+
+    let mut variants = Vec::new();
+    let mut display_arms = Vec::new();
+    let mut source_arms = Vec::new();
+
+    // A typestate builder cannot be built with missing fields.
+    if !target.options.typestate {
+        variants.push(quote! {
+            /// Names of the required fields that were never set.
+            MissingFields(#alloc::vec::Vec<&'static str>)
+        });
+        display_arms.push(quote! {
+            #error_name::MissingFields(fields) => {
+                f.write_str("missing required fields: ")?;
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    f.write_str(field)?;
+                }
+                ::core::result::Result::Ok(())
+            }
+        });
+        source_arms.push(quote! {
+            #error_name::MissingFields(_) => ::core::option::Option::None
+        });
+    }
+
+    if target.options.validate.is_some() {
+        variants.push(quote! {
+            /// The validator rejected the built value.
+            ValidationFailed(#boxed_error)
+        });
+        display_arms.push(quote! {
+            #error_name::ValidationFailed(err) => ::core::write!(f, "validation failed: {}", err)
+        });
+        source_arms.push(quote! {
+            #error_name::ValidationFailed(err) => ::core::option::Option::Some(&**err)
+        });
+    }
+
+    if fields.iter().any(|f| f.sub_builder.is_some()) {
+        variants.push(quote! {
+            /// Building the value of a `#[builder(sub_builder)]` field failed.
+            SubBuilder {
+                field: &'static str,
+                source: #boxed_error,
+            }
+        });
+        display_arms.push(quote! {
+            #error_name::SubBuilder { field, source } => ::core::write!(f, "{}: {}", field, source)
+        });
+        source_arms.push(quote! {
+            #error_name::SubBuilder { source, .. } => ::core::option::Option::Some(&**source)
+        });
+    }
+
+    if fields.iter().any(|f| f.async_init.is_some()) {
+        variants.push(quote! {
+            /// The future given to a `#[builder(setter(async_fn))]` setter
+            /// failed.
+            InitFailed {
+                field: &'static str,
+                source: #boxed_error,
+            }
+        });
+        display_arms.push(quote! {
+            #error_name::InitFailed { field, source } => {
+                ::core::write!(f, "initializing field `{}` failed: {}", field, source)
+            }
+        });
+        source_arms.push(quote! {
+            #error_name::InitFailed { source, .. } => ::core::option::Option::Some(&**source)
+        });
+    }
+
+    if fields.iter().any(|f| f.env.is_some()) {
+        variants.push(quote! {
+            /// An environment variable read by `fill_from_env` is not valid
+            /// unicode, or its value could not be parsed.
+            InvalidEnv {
+                var: &'static str,
+                field: &'static str,
+                source: #boxed_error,
+            }
+        });
+        display_arms.push(quote! {
+            #error_name::InvalidEnv { var, field, source } => ::core::write!(
+                f,
+                "invalid value of environment variable `{}` for field `{}`: {}",
+                var,
+                field,
+                source,
+            )
+        });
+        source_arms.push(quote! {
+            #error_name::InvalidEnv { source, .. } => ::core::option::Option::Some(&**source)
+        });
+    }
+
+    quote! {
+        #doc
+        #[derive(::core::fmt::Debug)]
+        #vis enum #error_name {
+            #(#variants,)*
+        }
+
+        impl ::core::fmt::Display for #error_name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                match self {
+                    #(#display_arms,)*
+                }
+            }
+        }
+
+        impl ::core::error::Error for #error_name {
+            fn source(&self) -> ::core::option::Option<&(dyn ::core::error::Error + 'static)> {
+                match self {
+                    #(#source_arms,)*
+                }
+            }
         }
     }
 }
 
+/// `HttpRequest` becomes `http_request`. A run of capitals is kept together as
+/// one word, so `HTTPRequest` becomes `http_request` too.
+fn snake_case(ident: &Ident) -> String {
+    let chars: Vec<char> = ident.unraw().to_string().chars().collect();
+    let mut snake = String::new();
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if (!prev.is_uppercase() && prev != '_') || (prev.is_uppercase() && next_lower) {
+                snake.push('_');
+            }
+        }
+        snake.extend(ch.to_lowercase());
+    }
+    snake
+}
+
+#[allow(dead_code)]
 fn pretty_print(ts: &proc_macro2::TokenStream) -> String {
     let file = syn::parse_file(&ts.to_string()).unwrap();
     prettyplease::unparse(&file)
 }
 
-/// Find T in an `Option<T>` declaration.
-/// See "tests/06-optional-field.rs" for the pattern.
-fn find_inner_type(ty: &Type) -> Option<&Type> {
-    let Type::Path(type_path) = ty else {
+/// `TlsConfig<T>` becomes `TlsConfigBuilder<T>`, the builder derived for it.
+fn sub_builder_type(ty: &Type) -> Option<Type> {
+    let Type::Path(TypePath { qself: None, path }) = ty else {
         return None;
     };
+    let mut path = path.clone();
+    let last = path.segments.last_mut()?;
+    last.ident = format_ident!("{}Builder", last.ident);
+    Some(Type::Path(TypePath { qself: None, path }))
+}
+
+/// How a one-at-a-time setter adds to a field of type `ty`, or `None` if `ty`
+/// does not look like a collection. Well-known collections get a setter taking
+/// their item. Other types with type arguments, and the string types, are left
+/// to the `Extend` bound of the generic setter; without type arguments a type
+/// like `u32` would silently become an optional field with an uncallable
+/// setter.
+fn collection_kind(ty: &Type) -> Option<Collection<'_>> {
+    let segment = last_segment(ty)?;
+    let args = type_args(segment);
+    match (segment.ident.to_string().as_str(), args.as_slice()) {
+        ("String" | "OsString" | "PathBuf", []) => Some(Collection::Other),
+        ("Option", _) | (_, []) => None,
+        ("HashMap" | "BTreeMap", [key, value, ..]) => Some(Collection::Map(key, value)),
+        ("Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet" | "BinaryHeap", [elem, ..]) => {
+            Some(Collection::Elements(elem))
+        }
+        _ => Some(Collection::Other),
+    }
+}
 
-    let Some(path_segment) = type_path.path.segments.first() else {
+/// The last segment of a path type, e.g. `HashMap<K, V>` for
+/// `std::collections::HashMap<K, V>`.
+fn last_segment(ty: &Type) -> Option<&PathSegment> {
+    let Type::Path(TypePath { qself: None, path }) = ty else {
         return None;
     };
+    path.segments.last()
+}
 
-    if path_segment.ident != "Option" {
-        return None;
-    }
+/// The type arguments of a path segment, e.g. `K` and `V` in `HashMap<K, V>`.
+fn type_args(segment: &PathSegment) -> Vec<&Type> {
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return Vec::new();
+    };
+    args.args
+        .iter()
+        .filter_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        })
+        .collect()
+}
 
-    let PathArguments::AngleBracketed(ref args) = path_segment.arguments else {
+/// Find T in `Option<T>`, which may also be written with its full path, e.g.
+/// `std::option::Option<T>`. See "tests/06-optional-field.rs" for the pattern.
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(TypePath { qself: None, path }) = ty else {
         return None;
     };
 
-    let Some(GenericArgument::Type(ref t)) = args.args.first() else {
+    let idents: Vec<_> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+    let idents: Vec<_> = idents.iter().map(String::as_str).collect();
+    if !matches!(
+        idents.as_slice(),
+        ["Option"] | ["option", "Option"] | ["std" | "core", "option", "Option"]
+    ) {
         return None;
-    };
+    }
 
-    Some(t)
+    match type_args(path.segments.last()?).as_slice() {
+        [inner] => Some(inner),
+        _ => None,
+    }
 }
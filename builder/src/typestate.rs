@@ -0,0 +1,260 @@
+//! `#[builder(typestate)]`: the builder records in its type which required
+//! fields have been set, so a missing field is a compile error instead of an
+//! error returned from `build`.
+//!
+//! Every required field gets a type parameter holding the field's value. It
+//! starts out as `()` and becomes the field's type once the setter was called:
+//!
+//! ```text
+//! pub struct CommandBuilder<__Executable = (), __Args = ()> {
+//!     executable: __Executable,
+//!     args: __Args,
+//!     current_dir: Option<String>,
+//! }
+//!
+//! impl CommandBuilder<String, Vec<String>> {
+//!     fn build(self) -> Command { ... }
+//! }
+//! ```
+
+use crate::{
+    doc, make_error_type, make_field_setters, make_round_trip, make_validated_return,
+    InternalField, Pattern, SkippedField, Target,
+};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{parse_quote, GenericParam, Generics};
+
+pub(crate) fn generate(
+    target: &Target,
+    fields: &[InternalField],
+    skipped: &[SkippedField],
+) -> TokenStream {
+    let Target {
+        struct_name,
+        builder_name,
+        factory_name,
+        constructor,
+        vis,
+        generics,
+        ..
+    } = target;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let states: Vec<_> = fields
+        .iter()
+        .map(|f| f.is_mandatory().then(|| state_param(&f.name)))
+        .collect();
+    let struct_args = generic_args(generics);
+
+    // Builder type with every required field in the given state.
+    let builder_ty = |state_of: &dyn Fn(usize, &Ident) -> TokenStream| {
+        let state_args = states
+            .iter()
+            .enumerate()
+            .filter_map(|(i, state)| state.as_ref().map(|state| state_of(i, state)));
+        quote! { #builder_name<#(#struct_args,)* #(#state_args),*> }
+    };
+
+    let mut builder_generics = (*generics).clone();
+    for state in states.iter().flatten() {
+        builder_generics
+            .params
+            .push(GenericParam::Type(parse_quote!(#state = ())));
+    }
+    let (builder_impl_generics, _, _) = builder_generics.split_for_impl();
+
+    let marker = target.marker_init();
+    let marker_field = target.marker_field();
+    let builder_fields = fields.iter().zip(&states).map(|(field, state)| {
+        let name = &field.name;
+        match state {
+            Some(state) => quote! { #name: #state },
+            None => {
+                let ty = field.get_core_type();
+                quote! { #name: ::core::option::Option<#ty> }
+            }
+        }
+    });
+
+    let initial_fields = fields.iter().map(|f| {
+        let name = &f.name;
+        if f.is_mandatory() {
+            quote! { #name: () }
+        } else {
+            quote! { #name: ::core::option::Option::None }
+        }
+    });
+    let unset_builder = builder_ty(&|_, _| quote!(()));
+
+    let generic_builder = builder_ty(&|_, state| quote!(#state));
+    let setters = fields.iter().enumerate().map(|(i, field)| {
+        if !field.is_mandatory() {
+            return make_field_setters(field, Pattern::Owned);
+        }
+
+        let name = &field.name;
+        let setter_name = &field.setter;
+        let setter_vis = &field.setter_vis;
+        let setter_doc = &field.doc;
+        let ty = field.ty;
+        let set_builder = builder_ty(&|j, state| if i == j { quote!(#ty) } else { quote!(#state) });
+        let other_names: Vec<_> = fields.iter().map(|f| &f.name).filter(|n| *n != name).collect();
+        let (params, value) = field.setter_params(ty);
+
+        let try_setter = field.try_setter.then(|| {
+            let try_name = field.try_setter_name();
+            quote! {
+                #setter_doc
+                #setter_vis fn #try_name<__T>(
+                    self,
+                    #name: __T,
+                ) -> ::core::result::Result<#set_builder, <__T as ::core::convert::TryInto<#ty>>::Error>
+                where
+                    __T: ::core::convert::TryInto<#ty>,
+                {
+                    ::core::result::Result::Ok(#builder_name {
+                        #name: ::core::convert::TryInto::try_into(#name)?,
+                        #(#other_names: self.#other_names,)*
+                        #marker
+                    })
+                }
+            }
+        });
+
+        quote! {
+            #setter_doc
+            #setter_vis fn #setter_name(self, #params) -> #set_builder {
+                #builder_name {
+                    #name: #value,
+                    #(#other_names: self.#other_names,)*
+                    #marker
+                }
+            }
+
+            #try_setter
+        }
+    });
+
+    let complete_builder = builder_ty(&|i, _| {
+        let ty = fields[i].ty;
+        quote!(#ty)
+    });
+    let round_trip = make_round_trip(target, fields, complete_builder.clone(), &|field, value| {
+        if field.is_mandatory() {
+            value
+        } else {
+            field.init_slot(value)
+        }
+    });
+    let error_name = &target.error_name;
+    let field_members = fields.iter().map(|f| &f.member);
+    let field_values = fields.iter().map(|f| {
+        let name = &f.name;
+        if f.is_mandatory() {
+            quote! { self.#name }
+        } else {
+            f.unwrap_slot(quote! { self.#name })
+        }
+    });
+    let value = quote! {
+        #constructor {
+            #(#field_members: #field_values,)*
+            #(#skipped,)*
+        }
+    };
+
+    // Only the validator can make building a complete builder fail.
+    let (build_ret, build_body, error) = if target.options.validate.is_some() {
+        (
+            quote! { ::core::result::Result<#struct_name #ty_generics, #error_name> },
+            make_validated_return(target, value),
+            Some(make_error_type(target, fields)),
+        )
+    } else {
+        (quote! { #struct_name #ty_generics }, value, None)
+    };
+
+    // Whether a field is set is known from the type, so there is no `Debug`
+    // impl or `missing_fields` reporting it.
+    let default_impl = target.make_default_impl(&unset_builder);
+    let derives = target.builder_derives();
+    let builder_vis = target.builder_vis();
+    let builder_doc = doc::builder(target, fields);
+    let factory_doc = doc::factory(target);
+    let build_doc = doc::build(target, fields);
+    quote! {
+        impl #impl_generics #struct_name #ty_generics #where_clause {
+            #factory_doc
+            #builder_vis fn #factory_name() -> #unset_builder {
+                #builder_name {
+                    #(#initial_fields,)*
+                    #marker
+                }
+            }
+        }
+
+        #builder_doc
+        #derives
+        #builder_vis struct #builder_name #builder_generics #where_clause {
+            #(#builder_fields,)*
+            #marker_field
+        }
+
+        impl #builder_impl_generics #generic_builder #where_clause {
+            #(#setters)*
+        }
+
+        impl #impl_generics #complete_builder #where_clause {
+            #build_doc
+            #vis fn build(self) -> #build_ret {
+                #build_body
+            }
+        }
+
+        #default_impl
+        #round_trip
+        #error
+    }
+}
+
+/// `host_name` becomes `__HostName`.
+fn state_param(field: &Ident) -> Ident {
+    let camel: String = field
+        .unraw()
+        .to_string()
+        .split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+        })
+        .collect();
+    format_ident!("__{}", camel)
+}
+
+/// The generic arguments naming each of the struct's generic parameters.
+fn generic_args(generics: &Generics) -> Vec<TokenStream> {
+    generics
+        .params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Type(param) => {
+                let ident = &param.ident;
+                quote!(#ident)
+            }
+            GenericParam::Lifetime(param) => {
+                let lifetime = &param.lifetime;
+                quote!(#lifetime)
+            }
+            GenericParam::Const(param) => {
+                let ident = &param.ident;
+                quote!(#ident)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,26 @@
+// Every problem with the input should be reported as a compile error pointing
+// at the offending tokens, and a macro should report all of the problems it
+// finds at once rather than stopping at the first one.
+//
+// Errors from several fields are combined with syn::Error::combine and lowered
+// to compile_error! invocations in one go.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(each = 1)]
+    executable: String,
+    #[builder(each = "arg")]
+    args: u32,
+    #[builder(eac = "env")]
+    env: Vec<String>,
+}
+
+#[derive(Builder)]
+pub union Number {
+    int: i32,
+    float: f32,
+}
+
+fn main() {}
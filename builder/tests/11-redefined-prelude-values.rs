@@ -0,0 +1,51 @@
+// Same idea as the previous prelude test, but this time the caller shadows the
+// value-namespace items and macros the expansion relies on. Constructors such
+// as Some and Ok, and macros such as format!, must also be referred to through
+// absolute paths for the generated setters and build method to keep working.
+// The same goes for derive macros like Debug on the generated error type.
+
+use derive_builder::Builder;
+#[allow(unused_imports)]
+use derive_builder::Builder as Debug;
+
+#[allow(non_upper_case_globals)]
+const Some: () = ();
+#[allow(non_upper_case_globals)]
+const None: () = ();
+#[allow(non_upper_case_globals)]
+const Ok: () = ();
+#[allow(non_upper_case_globals)]
+const Err: () = ();
+
+#[allow(unused_macros)]
+macro_rules! format {
+    ($($tt:tt)*) => {
+        ()
+    };
+}
+
+#[allow(unused_macros)]
+macro_rules! stringify {
+    ($($tt:tt)*) => {
+        ()
+    };
+}
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.args, vec!["build"]);
+
+    let err = Command::builder().build();
+    assert!(err.is_err());
+}
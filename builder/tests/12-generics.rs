@@ -0,0 +1,60 @@
+// Builders for generic structs need to carry the struct's generic parameters
+// along: the builder struct, the builder() factory and the builder's methods
+// all use the same lifetimes, type parameters, const parameters and where
+// clause as the original struct.
+//
+// Use syn::Generics::split_for_impl to get the three pieces that go into an
+// impl block:
+//
+//     impl #impl_generics Conn #ty_generics #where_clause {
+//         ...
+//     }
+//
+// Resources:
+//
+//   - Generics::split_for_impl:
+//     https://docs.rs/syn/1.0/syn/struct.Generics.html#method.split_for_impl
+
+use derive_builder::Builder;
+use std::fmt::Debug;
+
+pub trait Transport {
+    fn name(&self) -> &str;
+}
+
+pub struct Tcp;
+
+impl Transport for Tcp {
+    fn name(&self) -> &str {
+        "tcp"
+    }
+}
+
+#[derive(Builder)]
+pub struct Conn<'a, T: Transport, E = String, const N: usize = 4>
+where
+    T: Send,
+    E: Debug,
+{
+    host: &'a str,
+    transport: T,
+    #[builder(each = "extra")]
+    extras: Vec<E>,
+    retries: Option<[u8; N]>,
+}
+
+fn main() {
+    let host = String::from("localhost");
+    let conn: Conn<Tcp> = Conn::builder()
+        .host(&host)
+        .transport(Tcp)
+        .extra("keepalive".to_owned())
+        .retries([1, 2, 3, 4])
+        .build()
+        .unwrap();
+
+    assert_eq!(conn.host, "localhost");
+    assert_eq!(conn.transport.name(), "tcp");
+    assert_eq!(conn.extras, vec!["keepalive"]);
+    assert_eq!(conn.retries, Some([1, 2, 3, 4]));
+}
@@ -0,0 +1,40 @@
+// Instead of a boxed string, build() returns a dedicated error type generated
+// next to the builder. It lists every required field that was left unset, not
+// just the first one, so all of them can be reported to the user at once.
+//
+//     pub enum CommandBuilderError {
+//         MissingFields(Vec<&'static str>),
+//     }
+//
+// The error implements Display and std::error::Error so that it composes with
+// `?` and Box<dyn Error> like any other error type.
+
+use derive_builder::Builder;
+use std::error::Error;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn run() -> Result<Command, Box<dyn Error>> {
+    let command = Command::builder().build()?;
+    Ok(command)
+}
+
+fn main() {
+    let err = Command::builder().build().err().unwrap();
+    match &err {
+        CommandBuilderError::MissingFields(fields) => {
+            assert_eq!(fields, &["executable", "args"]);
+        }
+    }
+    assert_eq!(err.to_string(), "missing required fields: executable, args");
+
+    let err = Command::builder().args(vec![]).build().err().unwrap();
+    assert_eq!(err.to_string(), "missing required fields: executable");
+
+    assert!(run().is_err());
+}
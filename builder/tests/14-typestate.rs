@@ -0,0 +1,53 @@
+// With #[builder(typestate)] on the struct, the builder keeps track of which
+// required fields have been set in its own type. Every required field gets a
+// type parameter that starts out as () and turns into the field's type once
+// the corresponding setter has been called. build() is only implemented for
+// the builder type in which all of them are set, so it can return the struct
+// directly instead of a Result.
+//
+// Setters take the builder by value so that they can return a builder of a
+// different type.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    env: Vec<String>,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Conn<'a, T: Clone, const N: usize>
+where
+    T: Default,
+{
+    host: &'a str,
+    value: T,
+    ports: [u16; N],
+}
+
+fn main() {
+    let command: Command = Command::builder()
+        .arg("build".to_owned())
+        .env(vec![])
+        .executable("cargo".to_owned())
+        .arg("--release".to_owned())
+        .build();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert!(command.current_dir.is_none());
+
+    let conn = Conn::builder()
+        .ports([80, 443])
+        .host("localhost")
+        .value(1u8)
+        .build();
+    assert_eq!(conn.host, "localhost");
+    assert_eq!(conn.ports, [80, 443]);
+}
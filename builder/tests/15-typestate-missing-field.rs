@@ -0,0 +1,20 @@
+// Forgetting to set a required field of a typestate builder is a compile error:
+// there is no build() method for a builder type in which one of the state
+// parameters is still ().
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    env: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let _command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir("..".to_owned())
+        .build();
+}
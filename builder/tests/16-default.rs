@@ -0,0 +1,55 @@
+// Fields marked #[builder(default)] are no longer required: if their setter is
+// never called, build() falls back to Default::default(). With
+// #[builder(default = expr)] the given expression is used instead.
+//
+// The expression is only evaluated inside build(), and only if the field was
+// not set, so it may be arbitrarily expensive or have side effects.
+
+use derive_builder::Builder;
+use std::cell::Cell;
+
+thread_local! {
+    static CALLS: Cell<u32> = Cell::new(0);
+}
+
+fn default_dir() -> String {
+    CALLS.with(|calls| calls.set(calls.get() + 1));
+    "/tmp".to_owned()
+}
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(default)]
+    args: Vec<String>,
+    #[builder(default = vec!["PATH=/bin".to_owned()])]
+    env: Vec<String>,
+    #[builder(default = default_dir())]
+    current_dir: String,
+    #[builder(default = Some(3))]
+    retries: Option<u8>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+
+    assert!(command.args.is_empty());
+    assert_eq!(command.env, vec!["PATH=/bin"]);
+    assert_eq!(command.current_dir, "/tmp");
+    assert_eq!(command.retries, Some(3));
+    assert_eq!(CALLS.with(Cell::get), 1);
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir("..".to_owned())
+        .retries(0)
+        .build()
+        .unwrap();
+
+    assert_eq!(command.current_dir, "..");
+    assert_eq!(command.retries, Some(0));
+    assert_eq!(CALLS.with(Cell::get), 1);
+}
@@ -0,0 +1,17 @@
+// A default expression of the wrong type, or a field type without a Default
+// impl, is reported by rustc at the offending expression or type rather than
+// somewhere inside the generated code.
+
+use derive_builder::Builder;
+
+pub struct Handle;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(default = 80)]
+    executable: String,
+    #[builder(default)]
+    handle: Handle,
+}
+
+fn main() {}
@@ -0,0 +1,63 @@
+// #[builder(setter(into))] makes setters accept anything convertible into the
+// field type, so callers can write .executable("cargo") instead of
+// .executable("cargo".to_owned()). It can be given on the struct to apply to
+// every setter, or on individual fields.
+//
+// #[builder(try_setter)] additionally generates a try_<field> method taking
+// any value that implements TryInto<T> and returning the conversion error if
+// there is one.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(setter(into))]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+pub struct Server {
+    #[builder(setter(into))]
+    host: String,
+    #[builder(try_setter)]
+    port: u16,
+}
+
+#[derive(Builder)]
+#[builder(typestate, try_setter)]
+pub struct Limits {
+    #[builder(setter(into))]
+    name: String,
+    max: u8,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo")
+        .arg("build")
+        .arg(String::from("--release"))
+        .current_dir("..")
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+
+    let mut builder = Server::builder();
+    builder.host("localhost");
+    assert!(builder.try_port(70000u32).is_err());
+    let server = builder.try_port(8080u32).unwrap().build().unwrap();
+    assert_eq!(server.host, "localhost");
+    assert_eq!(server.port, 8080);
+
+    assert!(Limits::builder().name("requests").try_max(1000).is_err());
+    let limits = Limits::builder()
+        .name("requests")
+        .try_max(100)
+        .unwrap()
+        .build();
+    assert_eq!(limits.max, 100);
+}
@@ -0,0 +1,74 @@
+// By default setters take &mut self and build() moves the field values out of
+// the builder. #[builder(pattern = "...")] picks a different style:
+//
+//   - "owned": setters and build() take the builder by value, so a builder can
+//     be passed around and chained without a binding.
+//
+//   - "mutable": setters take &mut self, build() takes &self and clones the
+//     values, so the same builder can be built more than once.
+//
+//   - "immutable": setters take &self and return a modified copy of the
+//     builder, build() takes &self.
+//
+// Builders using the mutable and immutable patterns implement Clone so they
+// can be used as templates for several similar values.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Owned {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "mutable", try_setter)]
+pub struct Mutable {
+    executable: String,
+    retries: u8,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable")]
+pub struct Immutable {
+    executable: String,
+    current_dir: Option<String>,
+}
+
+fn with_args(builder: OwnedBuilder) -> OwnedBuilder {
+    builder.arg("build".to_owned()).arg("--release".to_owned())
+}
+
+fn main() {
+    let owned = with_args(Owned::builder())
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(owned.args, vec!["build", "--release"]);
+
+    let mut builder = Mutable::builder();
+    builder
+        .executable("cargo".to_owned())
+        .try_retries(3u32)
+        .unwrap();
+    let first = builder.build().unwrap();
+    let second = builder.build().unwrap();
+    assert_eq!(first.executable, second.executable);
+    assert_eq!(second.retries, 3);
+
+    let mut template = builder.clone();
+    template.executable("rustc".to_owned());
+    assert_eq!(template.build().unwrap().executable, "rustc");
+    assert_eq!(builder.build().unwrap().executable, "cargo");
+
+    let base = Immutable::builder().executable("cargo".to_owned());
+    let in_parent = base.current_dir("..".to_owned());
+    assert!(base.build().unwrap().current_dir.is_none());
+    assert_eq!(
+        in_parent.build().unwrap().current_dir.as_deref(),
+        Some("..")
+    );
+    assert_eq!(in_parent.build().unwrap().executable, "cargo");
+}
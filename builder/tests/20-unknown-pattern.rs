@@ -0,0 +1,18 @@
+// Unknown patterns, and patterns combined with the typestate builder which
+// always takes the builder by value, are rejected.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(pattern = "borrowed")]
+pub struct Command {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(typestate, pattern = "owned")]
+pub struct Server {
+    host: String,
+}
+
+fn main() {}
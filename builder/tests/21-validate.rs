@@ -0,0 +1,80 @@
+// #[builder(validate = "path::to::fn")] runs a function on the assembled value
+// before build() returns it. The validator takes the value by reference and
+// returns Result<(), E> for any E that converts into
+// Box<dyn Error + Send + Sync>, such as String or a custom error type.
+//
+// A rejected value is reported through the ValidationFailed variant of the
+// builder's error type, with the validator's error as its source().
+
+use derive_builder::Builder;
+use std::error::Error;
+
+#[derive(Builder)]
+#[builder(validate = "validate_command")]
+pub struct Command {
+    executable: String,
+    #[builder(default)]
+    args: Vec<String>,
+}
+
+fn validate_command(command: &Command) -> Result<(), String> {
+    if command.executable.is_empty() {
+        return Err("executable must not be empty".to_owned());
+    }
+    Ok(())
+}
+
+mod checks {
+    #[derive(Debug)]
+    pub struct InvalidRange;
+
+    impl std::fmt::Display for InvalidRange {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("min is larger than max")
+        }
+    }
+
+    impl std::error::Error for InvalidRange {}
+
+    pub fn range(range: &super::Range) -> Result<(), InvalidRange> {
+        if range.min > range.max {
+            return Err(InvalidRange);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Builder)]
+#[builder(typestate, validate = "checks::range")]
+pub struct Range {
+    min: u32,
+    max: u32,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+
+    let err = Command::builder()
+        .executable(String::new())
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "validation failed: executable must not be empty",
+    );
+    assert!(matches!(err, CommandBuilderError::ValidationFailed(_)));
+
+    let err = Command::builder().build().err().unwrap();
+    assert!(matches!(err, CommandBuilderError::MissingFields(_)));
+
+    let range = Range::builder().min(1).max(2).build().unwrap();
+    assert_eq!(range.max, 2);
+
+    let err = Range::builder().min(3).max(2).build().err().unwrap();
+    assert_eq!(err.source().unwrap().to_string(), "min is larger than max");
+}
@@ -0,0 +1,32 @@
+// Tuple structs get a builder too. Their setters are named after the field
+// positions, _0, _1 and so on, unless a field is given a name with
+// #[builder(name = "...")].
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Rgb(u8, u8, u8);
+
+#[derive(Builder)]
+pub struct Labeled<T>(
+    #[builder(name = "label")] String,
+    #[builder(name = "value")] T,
+    #[builder(default)] Vec<String>,
+);
+
+fn main() {
+    let color = Rgb::builder()._0(255)._1(128)._2(0).build().unwrap();
+    assert_eq!((color.0, color.1, color.2), (255, 128, 0));
+
+    let err = Rgb::builder()._1(0).build().err().unwrap();
+    assert_eq!(err.to_string(), "missing required fields: _0, _2");
+
+    let labeled = Labeled::builder()
+        .label("answer".to_owned())
+        .value(42)
+        .build()
+        .unwrap();
+    assert_eq!(labeled.0, "answer");
+    assert_eq!(labeled.1, 42);
+    assert!(labeled.2.is_empty());
+}
@@ -0,0 +1,76 @@
+// Deriving Builder on an enum generates a separate builder for every variant.
+// The builder for variant Circle of enum Shape is called ShapeCircleBuilder
+// and is created with Shape::circle_builder(); its build() method returns a
+// Shape. Variants with named fields, tuple variants and unit variants are all
+// supported, and the enum's generic parameters are carried along even into
+// builders of variants that do not use them.
+//
+// A run of capitals in a variant name counts as one word: the builder for
+// HTTPRequest is created with http_request_builder().
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+pub enum Shape {
+    Circle {
+        radius: f64,
+        #[builder(default)]
+        filled: bool,
+    },
+    Rect(f64, f64),
+    Empty,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(setter(into))]
+pub enum Message<T> {
+    Text { body: String },
+    Payload(T),
+    HeartBeat,
+    HTTPRequest { url: String },
+}
+
+fn main() {
+    let circle = Shape::circle_builder().radius(1.5).build().unwrap();
+    assert_eq!(
+        circle,
+        Shape::Circle {
+            radius: 1.5,
+            filled: false,
+        },
+    );
+
+    let rect = Shape::rect_builder()._0(2.0)._1(3.0).build().unwrap();
+    assert_eq!(rect, Shape::Rect(2.0, 3.0));
+
+    let empty = Shape::empty_builder().build().unwrap();
+    assert_eq!(empty, Shape::Empty);
+
+    let err = ShapeCircleBuilder::build(&mut Shape::circle_builder());
+    assert!(matches!(
+        err,
+        Err(ShapeCircleBuilderError::MissingFields(_))
+    ));
+
+    let text: Message<u32> = Message::text_builder().body("hi").build().unwrap();
+    assert_eq!(
+        text,
+        Message::Text {
+            body: "hi".to_owned()
+        }
+    );
+
+    let payload: Message<u32> = Message::payload_builder()._0(7u32).build().unwrap();
+    assert_eq!(payload, Message::Payload(7));
+
+    let beat: Message<()> = Message::heart_beat_builder().build().unwrap();
+    assert_eq!(beat, Message::HeartBeat);
+
+    let request: Message<()> = Message::http_request_builder().url("/").build().unwrap();
+    assert_eq!(
+        request,
+        Message::HTTPRequest {
+            url: "/".to_owned()
+        }
+    );
+}
@@ -0,0 +1,15 @@
+// Builder options apply to the whole enum; attributes on individual variants
+// are rejected.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub enum Shape {
+    #[builder(setter(into))]
+    Circle {
+        radius: f64,
+    },
+    Rect(f64, f64),
+}
+
+fn main() {}
@@ -0,0 +1,69 @@
+// Generate `into_builder(self)` and `to_builder(&self)` on the struct, which
+// return a builder with every field already set from an existing value. This
+// makes it easy to derive a slightly different value from one that was
+// already built:
+//
+//     let release = debug.to_builder().arg("--release".to_owned()).build()?;
+//
+// `to_builder` clones the fields and is only available if all of them
+// implement Clone. `into_builder` consumes the value and works for any field
+// type.
+
+use derive_builder::Builder;
+use std::fs::File;
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(default = 3)]
+    retries: u8,
+}
+
+#[derive(Builder)]
+pub struct Log {
+    file: File,
+    path: String,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(typestate)]
+pub struct Point(i32, Option<i32>);
+
+fn main() {
+    let mut builder = Command::builder();
+    builder
+        .executable("cargo".to_owned())
+        .arg("build".to_owned());
+    let debug = builder.build().unwrap();
+
+    let release = debug
+        .to_builder()
+        .arg("--release".to_owned())
+        .retries(1)
+        .build()
+        .unwrap();
+    assert_eq!(release.executable, "cargo");
+    assert_eq!(release.args, vec!["build", "--release"]);
+    assert_eq!(release.current_dir, None);
+    assert_eq!(release.retries, 1);
+    assert_eq!(debug.retries, 3);
+
+    let same = debug.into_builder().build().unwrap();
+    assert_eq!(same.args, vec!["build"]);
+    assert_eq!(same.retries, 3);
+
+    let log = Log::builder()
+        .file(File::open("Cargo.toml").unwrap())
+        .path("Cargo.toml".to_owned())
+        .build()
+        .unwrap();
+    let moved = log.into_builder().path("moved".to_owned()).build().unwrap();
+    assert_eq!(moved.path, "moved");
+
+    let point = Point::builder()._0(1)._1(2).build();
+    assert_eq!(point.to_builder()._0(5).build(), Point(5, Some(2)));
+    assert_eq!(point.into_builder().build(), Point(1, Some(2)));
+}
@@ -0,0 +1,113 @@
+// A field whose type also derives Builder can be given
+// #[builder(sub_builder)]. Its setter takes no argument and instead returns a
+// mutable reference to the field's own builder, created on first use:
+//
+//     builder.tls().cert("cert.pem".to_owned()).key("key.pem".to_owned());
+//
+// The outer build() runs the inner builder's build(). If the field's setter
+// was never called, an empty inner builder is built, so the field's required
+// fields are still reported. An error from the inner builder is returned as
+//
+//     ServerBuilderError::SubBuilder { field: "tls", source }
+//
+// and displayed prefixed with the field name, which gives the full path to
+// the offending field when sub-builders are nested.
+//
+// With the "mutable" pattern the outer build() clones the inner builder, which
+// therefore has to implement Clone as well.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Clone, Debug, PartialEq)]
+#[builder(pattern = "mutable")]
+pub struct Endpoint {
+    host: String,
+    port: u16,
+}
+
+#[derive(Builder, Clone, Debug, PartialEq)]
+pub struct TlsConfig {
+    cert: String,
+    key: String,
+    verify: Option<bool>,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Server {
+    name: String,
+    #[builder(sub_builder)]
+    tls: TlsConfig,
+    #[builder(sub_builder)]
+    upstream: Proxy,
+}
+
+#[derive(Builder, Clone, Debug, PartialEq)]
+pub struct Proxy {
+    #[builder(sub_builder)]
+    endpoint: Endpoint,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "mutable")]
+pub struct Template {
+    #[builder(sub_builder)]
+    endpoint: Endpoint,
+}
+
+fn main() {
+    let mut builder = Server::builder();
+    builder.name("web".to_owned());
+    builder
+        .tls()
+        .cert("cert.pem".to_owned())
+        .key("key.pem".to_owned());
+    builder.tls().verify(true);
+    builder
+        .upstream()
+        .endpoint()
+        .host("localhost".to_owned())
+        .port(8080);
+
+    let server = builder.build().unwrap();
+    assert_eq!(server.tls.cert, "cert.pem");
+    assert_eq!(server.tls.key, "key.pem");
+    assert_eq!(server.tls.verify, Some(true));
+    assert_eq!(server.upstream.endpoint.port, 8080);
+
+    let mut builder = server.into_builder();
+    builder.upstream().endpoint().port(9090);
+    let server = builder.build().unwrap();
+    assert_eq!(server.upstream.endpoint.port, 9090);
+    assert_eq!(server.tls.cert, "cert.pem");
+
+    let mut builder = Server::builder();
+    builder.name("web".to_owned());
+    builder.tls().cert("cert.pem".to_owned());
+    builder.upstream().endpoint().host("localhost".to_owned());
+    let err = builder.build().unwrap_err();
+    match &err {
+        ServerBuilderError::SubBuilder { field, .. } => assert_eq!(*field, "tls"),
+        _ => panic!("expected a sub-builder error"),
+    }
+    assert_eq!(err.to_string(), "tls: missing required fields: key");
+
+    let mut builder = Server::builder();
+    builder.name("web".to_owned());
+    builder
+        .tls()
+        .cert("cert.pem".to_owned())
+        .key("key.pem".to_owned());
+    let err = builder.build().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "upstream: endpoint: missing required fields: host, port",
+    );
+
+    let mut template = Template::builder();
+    template.endpoint().host("localhost".to_owned()).port(80);
+    let first = template.build().unwrap();
+    template.endpoint().port(443);
+    let second = template.build().unwrap();
+    assert_eq!(first.endpoint.port, 80);
+    assert_eq!(second.endpoint.port, 443);
+}
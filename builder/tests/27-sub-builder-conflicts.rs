@@ -0,0 +1,27 @@
+// A sub-builder is modified through `&mut` references, so it cannot be used
+// with builders whose setters take the builder by value. It also replaces the
+// field's setter, so setter options on the same field are rejected.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Endpoint {
+    host: String,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Owned {
+    #[builder(sub_builder)]
+    endpoint: Endpoint,
+}
+
+#[derive(Builder)]
+pub struct Conflicting {
+    #[builder(sub_builder, setter(into))]
+    endpoint: Endpoint,
+    #[builder(sub_builder)]
+    optional: Option<Endpoint>,
+}
+
+fn main() {}
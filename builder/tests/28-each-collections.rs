@@ -0,0 +1,90 @@
+// #[builder(each = "...")] is not limited to Vec. The one-at-a-time setter
+// adds an item to whatever collection the field holds:
+//
+//   - HashMap<K, V> and BTreeMap<K, V>: the setter takes a key and a value,
+//     e.g. `header(key, value)`.
+//
+//   - Vec<T>, VecDeque<T>, LinkedList<T>, HashSet<T>, BTreeSet<T> and
+//     BinaryHeap<T>: the setter takes one element.
+//
+//   - String, OsString, PathBuf and any other type with type arguments that
+//     implements Default and Extend<T>, e.g. a generic alias of the
+//     collections above: the setter is generic over the item type T. Types
+//     without type arguments are rejected, so that `each` on a scalar field is
+//     an error instead of a setter that can never be called.
+//
+// #[builder(extend)] additionally generates an `extend_<field>` setter taking
+// anything that can be iterated over. Like with `each`, a field with this
+// attribute is empty if nothing was added to it.
+
+use derive_builder::Builder;
+use std::collections::{BTreeSet, HashMap};
+
+pub type Params<V> = HashMap<String, V>;
+
+#[derive(Builder)]
+pub struct Request {
+    url: String,
+    #[builder(each = "header", setter(into))]
+    headers: HashMap<String, String>,
+    #[builder(each = "tag", extend)]
+    tags: BTreeSet<&'static str>,
+    #[builder(each = "part")]
+    parts: Parts<u8>,
+    #[builder(extend)]
+    body: std::vec::Vec<u8>,
+    #[builder(each = "segment")]
+    path: String,
+    #[builder(each = "param")]
+    params: Params<String>,
+}
+
+#[derive(Default)]
+pub struct Parts<T>(Vec<T>);
+
+impl<T> Extend<T> for Parts<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+fn main() {
+    let request = Request::builder()
+        .url("https://example.com".to_owned())
+        .header("Accept", "text/html")
+        .header("Host", "example.com")
+        .tag("b")
+        .tag("a")
+        .extend_tags(["c", "a"])
+        .part(1)
+        .part(2)
+        .extend_body(b"hello".iter().copied())
+        .extend_body(vec![b'!'])
+        .segment("/index")
+        .segment('/')
+        .param(("page".to_owned(), "2".to_owned()))
+        .build()
+        .unwrap();
+
+    assert_eq!(request.headers.len(), 2);
+    assert_eq!(request.headers["Host"], "example.com");
+    assert_eq!(
+        request.tags.into_iter().collect::<Vec<_>>(),
+        ["a", "b", "c"]
+    );
+    assert_eq!(request.parts.0, [1, 2]);
+    assert_eq!(request.body, b"hello!");
+    assert_eq!(request.path, "/index/");
+    assert_eq!(request.params["page"], "2");
+
+    let empty = Request::builder()
+        .url("https://example.com".to_owned())
+        .build()
+        .unwrap();
+    assert!(empty.headers.is_empty());
+    assert!(empty.tags.is_empty());
+    assert!(empty.parts.0.is_empty());
+    assert!(empty.body.is_empty());
+    assert!(empty.path.is_empty());
+    assert!(empty.params.is_empty());
+}
@@ -0,0 +1,71 @@
+// Optional fields are recognized by the last segment of their type's path, so
+// `std::option::Option<T>` and `core::option::Option<T>` are optional just like
+// `Option<T>`. A type that merely happens to be called Option in some other
+// module is not.
+//
+// For an `Option<Option<T>>` field only the outer Option is stripped: the
+// setter takes an `Option<T>`, and the field is `None` if the setter was never
+// called.
+//
+// #[builder(setter(strip_option = false))] keeps the setter of an optional
+// field taking the whole `Option<T>`, for callers that already hold one.
+
+use derive_builder::Builder;
+
+mod config {
+    pub type Option<T> = std::vec::Vec<T>;
+}
+
+#[derive(Builder, Debug)]
+pub struct Command {
+    executable: String,
+    current_dir: std::option::Option<String>,
+    timeout: ::core::option::Option<u64>,
+    env: config::Option<String>,
+    exit_code: Option<Option<i32>>,
+    #[builder(setter(strip_option = false))]
+    user: Option<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .env(vec!["RUST_LOG=debug".to_owned()])
+        .build()
+        .unwrap();
+    assert_eq!(command.current_dir, None);
+    assert_eq!(command.timeout, None);
+    assert_eq!(command.exit_code, None);
+    assert_eq!(command.user, None);
+
+    let user = std::env::var("BUILDER_TEST_USER").ok();
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir("..".to_owned())
+        .timeout(30)
+        .env(Vec::new())
+        .exit_code(None)
+        .user(user.clone())
+        .build()
+        .unwrap();
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+    assert_eq!(command.timeout, Some(30));
+    assert_eq!(command.exit_code, Some(None));
+    assert_eq!(command.user, user);
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .env(Vec::new())
+        .exit_code(Some(0))
+        .user(Some("root".to_owned()))
+        .build()
+        .unwrap();
+    assert_eq!(command.exit_code, Some(Some(0)));
+    assert_eq!(command.user.as_deref(), Some("root"));
+
+    let err = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap_err();
+    assert_eq!(err.to_string(), "missing required fields: env");
+}
@@ -0,0 +1,62 @@
+// Setters and build() have the same visibility as the struct, so a builder
+// derived inside a module can be used from outside of it.
+//
+// The builder itself can be customized with struct attributes:
+//
+//   - #[builder(name = "...")] renames the builder. The error type is named
+//     after the builder with an `Error` suffix.
+//
+//   - #[builder(vis = "...")] sets the visibility of the builder and its error
+//     type, which are `pub` by default.
+//
+//   - #[builder(derive(...))] derives additional traits for the builder.
+//
+// #[builder(setter(vis = "..."))] on the struct or a field overrides the
+// visibility of the setters, e.g. to keep a field internal to its module.
+
+mod config {
+    use derive_builder::Builder;
+
+    #[derive(Builder, Debug)]
+    #[builder(name = "ConfigFactory", vis = "pub(crate)", derive(Debug, Clone))]
+    pub struct Config {
+        pub name: String,
+        pub port: Option<u16>,
+        #[builder(default = 1, setter(vis = ""))]
+        pub version: u8,
+    }
+
+    pub fn next_version(mut factory: ConfigFactory) -> ConfigFactory {
+        factory.version(2);
+        factory
+    }
+
+    #[derive(Builder)]
+    #[builder(typestate, setter(vis = "pub(crate)"))]
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+}
+
+use config::{Config, ConfigFactory, ConfigFactoryError};
+
+fn main() {
+    let mut factory: ConfigFactory = Config::builder();
+    factory.name("server".to_owned()).port(8080);
+    let copy = factory.clone();
+    assert!(format!("{:?}", copy).contains("server"));
+
+    let config = factory.build().unwrap();
+    assert_eq!(config.port, Some(8080));
+    assert_eq!(config.version, 1);
+
+    let config = config::next_version(copy).build().unwrap();
+    assert_eq!(config.version, 2);
+
+    let err: ConfigFactoryError = Config::builder().build().unwrap_err();
+    assert_eq!(err.to_string(), "missing required fields: name");
+
+    let point = config::Point::builder().x(1).y(2).build();
+    assert_eq!((point.x, point.y), (1, 2));
+}
@@ -0,0 +1,20 @@
+// A setter made private with #[builder(setter(vis = ""))] cannot be called
+// from outside the module, even though the other setters can.
+
+mod config {
+    use derive_builder::Builder;
+
+    #[derive(Builder)]
+    pub struct Config {
+        name: String,
+        #[builder(default, setter(vis = ""))]
+        secret: String,
+    }
+}
+
+fn main() {
+    let _ = config::Config::builder()
+        .name("server".to_owned())
+        .secret("hunter2".to_owned())
+        .build();
+}
@@ -0,0 +1,43 @@
+// Fields that callers should never set, like caches or handles, can be given
+// #[builder(skip)]. The builder has no slot or setter for them, and build()
+// initializes them with Default::default(), or with the given expression for
+// #[builder(skip = expr)].
+//
+// to_builder() and into_builder() leave skipped fields behind, so a value
+// built from the returned builder gets freshly initialized ones.
+
+use derive_builder::Builder;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+#[derive(Builder)]
+pub struct Resolver {
+    server: String,
+    #[builder(skip)]
+    cache: RefCell<HashMap<String, String>>,
+    #[builder(skip = Vec::with_capacity(16))]
+    pending: Vec<String>,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(typestate)]
+pub struct Counter(String, #[builder(skip = 1)] u32);
+
+fn main() {
+    let mut builder = Resolver::builder();
+    builder.server("1.1.1.1".to_owned());
+    let resolver = builder.build().unwrap();
+    assert!(resolver.cache.borrow().is_empty());
+    assert!(resolver.pending.capacity() >= 16);
+
+    resolver
+        .cache
+        .borrow_mut()
+        .insert("example.com".to_owned(), "93.184.216.34".to_owned());
+    let fresh = resolver.to_builder().build().unwrap();
+    assert_eq!(fresh.server, "1.1.1.1");
+    assert!(fresh.cache.borrow().is_empty());
+
+    let counter = Counter::builder()._0("requests".to_owned()).build();
+    assert_eq!(counter, Counter("requests".to_owned(), 1));
+}
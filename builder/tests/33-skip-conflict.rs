@@ -0,0 +1,13 @@
+// A skipped field has no setter, so setter options on it make no sense and
+// are rejected.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Resolver {
+    server: String,
+    #[builder(skip, setter(into))]
+    cache: Vec<String>,
+}
+
+fn main() {}
@@ -0,0 +1,65 @@
+// The generated items are documented, so that the builder shows up properly
+// in rustdoc and passes #![deny(missing_docs)]:
+//
+//   - The `///` comments of a field are copied onto its setters. A field can
+//     instead be given #[builder(doc = "...")] for the setters.
+//
+//   - The builder struct explains where it comes from and lists the required
+//     fields, unless the struct has #[builder(doc = "...")].
+//
+//   - build() lists the required fields and the ways it can fail, and the
+//     associated functions creating a builder say what they return.
+
+//! Documented crate.
+
+#![deny(missing_docs)]
+
+use derive_builder::Builder;
+
+/// A process to run.
+#[derive(Builder)]
+#[builder(try_setter)]
+pub struct Command {
+    /// The program to run.
+    pub executable: String,
+    /// Arguments passed to the program.
+    #[builder(each = "arg", extend)]
+    pub args: Vec<String>,
+    #[builder(doc = "Working directory of the process.")]
+    current_dir: Option<String>,
+}
+
+/// A point in the plane.
+#[derive(Builder)]
+#[builder(typestate, doc = "Builds a [`Point`] one coordinate at a time.")]
+pub struct Point {
+    /// Horizontal coordinate.
+    pub x: i32,
+    /// Vertical coordinate.
+    pub y: i32,
+}
+
+/// A shape.
+#[derive(Builder)]
+pub enum Shape {
+    /// A circle.
+    Circle {
+        /// The radius.
+        radius: u32,
+    },
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.args, ["build"]);
+
+    let point = Point::builder().x(1).y(2).build();
+    assert_eq!(point.x + point.y, 3);
+
+    let circle = Shape::circle_builder().radius(1).build().unwrap();
+    assert!(matches!(circle, Shape::Circle { radius: 1 }));
+}
@@ -0,0 +1,74 @@
+// Crates without the standard library can use the builder with
+// #[builder(no_std)]. The generated code then only refers to `core` and
+// `alloc`, so the crate needs an `extern crate alloc;`. The error type
+// implements `core::error::Error`.
+//
+// This test links std under a different name only to get a `main` function;
+// any `::std` path emitted by the macro would fail to resolve.
+
+#![no_std]
+
+extern crate alloc;
+extern crate std as host;
+
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(no_std, validate = "check_baud_rate")]
+pub struct Uart {
+    name: String,
+    baud_rate: u32,
+    #[builder(each = "pin")]
+    pins: Vec<u8>,
+    parity: Option<bool>,
+    #[builder(sub_builder)]
+    buffer: Buffer,
+}
+
+#[derive(Builder, Debug)]
+#[builder(no_std)]
+pub struct Buffer {
+    size: usize,
+}
+
+fn check_baud_rate(uart: &Uart) -> Result<(), String> {
+    if uart.baud_rate % 300 == 0 {
+        Ok(())
+    } else {
+        Err("unsupported baud rate".to_owned())
+    }
+}
+
+fn assert_error<E: core::error::Error>(_: &E) {}
+
+fn main() {
+    let mut builder = Uart::builder();
+    builder
+        .name("uart0".to_owned())
+        .baud_rate(115_200)
+        .pin(1)
+        .pin(2);
+    builder.buffer().size(64);
+    let uart = builder.build().unwrap();
+    assert_eq!(uart.pins, [1, 2]);
+    assert_eq!(uart.parity, None);
+    assert_eq!(uart.buffer.size, 64);
+
+    let err = Uart::builder().build().unwrap_err();
+    assert_error(&err);
+    assert_eq!(err.to_string(), "missing required fields: name, baud_rate");
+
+    let mut builder = Uart::builder();
+    builder.name("uart1".to_owned()).baud_rate(1000);
+    let err = builder.build().unwrap_err();
+    assert_eq!(err.to_string(), "buffer: missing required fields: size");
+
+    let mut builder = Uart::builder();
+    builder.name("uart1".to_owned()).baud_rate(1000);
+    builder.buffer().size(8);
+    let err = builder.build().unwrap_err();
+    assert_eq!(err.to_string(), "validation failed: unsupported baud rate");
+}
@@ -0,0 +1,60 @@
+// The names and inputs of setters can be adjusted:
+//
+//   - #[builder(setter(prefix = "with_"))] on the struct prepends a prefix to
+//     every setter, so a field `port` gets a setter `with_port`. Setters added
+//     by `each` keep the name they were given.
+//
+//   - #[builder(setter(name = "..."))] on a field renames just its setter. It
+//     takes precedence over the prefix.
+//
+//   - #[builder(setter(transform = |s: &str| ...))] lets a setter take the
+//     closure's parameters instead of the field's type. The setter stores what
+//     the closure returns. Every parameter needs a type annotation.
+
+use derive_builder::Builder;
+use std::time::Duration;
+
+#[derive(Builder)]
+#[builder(setter(prefix = "with_"), try_setter)]
+pub struct Connection {
+    host: String,
+    port: u16,
+    #[builder(setter(name = "timeout_after"))]
+    timeout: Option<Duration>,
+    #[builder(setter(transform = |secs: u64, millis: u32| {
+        Duration::from_secs(secs) + Duration::from_millis(millis.into())
+    }))]
+    keep_alive: Duration,
+    #[builder(each = "header")]
+    headers: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Address {
+    #[builder(setter(transform = |s: &str| s.parse().unwrap()))]
+    ip: std::net::IpAddr,
+    #[builder(setter(name = "at_port"))]
+    port: u16,
+}
+
+fn main() {
+    let mut builder = Connection::builder();
+    builder
+        .with_host("localhost".to_owned())
+        .try_with_port(8080u32)
+        .unwrap()
+        .timeout_after(Duration::from_secs(5))
+        .with_keep_alive(1, 500)
+        .header("Accept: */*".to_owned());
+    let connection = builder.build().unwrap();
+    assert_eq!(connection.host, "localhost");
+    assert_eq!(connection.port, 8080);
+    assert_eq!(connection.timeout, Some(Duration::from_secs(5)));
+    assert_eq!(connection.keep_alive, Duration::from_millis(1500));
+    assert_eq!(connection.headers, ["Accept: */*"]);
+
+    let address = Address::builder().ip("127.0.0.1").at_port(80).build();
+    assert!(address.ip.is_loopback());
+    assert_eq!(address.port, 80);
+}
@@ -0,0 +1,22 @@
+// The parameters of a setter transform become the setter's parameters, so
+// they need type annotations. A transform already converts its input, so it
+// cannot be combined with setter(into). A setter prefix must produce valid
+// method names.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Connection {
+    #[builder(setter(transform = |s| s.to_owned()))]
+    host: String,
+    #[builder(setter(into, transform = |port: u16| port))]
+    port: u16,
+}
+
+#[derive(Builder)]
+#[builder(setter(prefix = "with-"))]
+pub struct Address {
+    ip: String,
+}
+
+fn main() {}
@@ -0,0 +1,97 @@
+// With #[builder(serde)] the builder implements serde's Deserialize, so it can
+// be read from a config file. Every field is optional in the input; fields
+// that are missing stay unset. The crate deriving the builder needs a
+// dependency on serde with the "derive" feature.
+//
+// The builder also gets
+//
+//     fn merge(&mut self, other: CommandBuilder)
+//
+// which sets every field that is set in `other`. Builders filled from a file,
+// from the environment and from code can be layered that way, and build()
+// then reports the required fields that none of the layers provided.
+//
+// A sub_builder field is merged field by field, so its builder needs
+// #[builder(serde)] as well.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(serde)]
+pub struct Limits {
+    memory: u64,
+    cpus: Option<u32>,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(serde)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(default = 3)]
+    retries: u32,
+    #[builder(sub_builder)]
+    limits: Limits,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(serde, pattern = "owned")]
+pub struct Tagged<T> {
+    tag: T,
+}
+
+fn main() {
+    let file: CommandBuilder = serde_json::from_str(
+        r#"{
+            "executable": "cargo",
+            "args": ["build"],
+            "limits": { "memory": 1024 }
+        }"#,
+    )
+    .unwrap();
+    let env: CommandBuilder =
+        serde_json::from_str(r#"{ "retries": 5, "limits": { "cpus": 4 } }"#).unwrap();
+
+    let mut builder = Command::builder();
+    builder.merge(file);
+    builder.merge(env);
+    builder.current_dir("..".to_owned());
+
+    let command = builder.build().unwrap();
+    assert_eq!(
+        command,
+        Command {
+            executable: "cargo".to_owned(),
+            args: vec!["build".to_owned()],
+            current_dir: Some("..".to_owned()),
+            retries: 5,
+            limits: Limits {
+                memory: 1024,
+                cpus: Some(4),
+            },
+        }
+    );
+
+    // Later layers win, fields they leave unset keep their value.
+    let mut builder: CommandBuilder =
+        serde_json::from_str(r#"{ "executable": "cargo", "retries": 1 }"#).unwrap();
+    let mut overrides = Command::builder();
+    overrides.executable("rustc".to_owned());
+    overrides.limits().memory(64);
+    builder.merge(overrides);
+    let command = builder.build().unwrap();
+    assert_eq!(command.executable, "rustc");
+    assert_eq!(command.retries, 1);
+    assert_eq!(command.limits.memory, 64);
+
+    // Required fields missing from every layer are reported by build().
+    let mut builder: CommandBuilder = serde_json::from_str(r#"{ "executable": "cargo" }"#).unwrap();
+    let err = builder.build().unwrap_err();
+    assert_eq!(err.to_string(), "limits: missing required fields: memory");
+
+    let mut tagged: TaggedBuilder<u8> = serde_json::from_str("{}").unwrap();
+    tagged.merge(serde_json::from_str(r#"{ "tag": 7 }"#).unwrap());
+    assert_eq!(tagged.build().unwrap(), Tagged { tag: 7 });
+}
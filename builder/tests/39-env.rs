@@ -0,0 +1,91 @@
+// Fields with #[builder(env = "APP_PORT")] can be read from the environment.
+// The builder gets
+//
+//     fn from_env() -> Result<ServiceBuilder, ServiceBuilderError>
+//     fn fill_from_env(&mut self) -> Result<(), ServiceBuilderError>
+//
+// which parse the value of each variable that is set with `FromStr` and store
+// it like the field's setter would. Variables that are not set leave the field
+// alone, so build() still reports it if it is required and nothing else set
+// it.
+//
+// An `Option<T>` field parses `T`, also with setter(strip_option = false).
+//
+// A value that does not parse fails with
+//
+//     ServiceBuilderError::InvalidEnv { var, field, source }
+//
+// naming both the variable and the field, with the parse error as source.
+
+use derive_builder::Builder;
+use std::error::Error;
+use std::net::IpAddr;
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Service {
+    #[builder(env = "APP_NAME")]
+    name: String,
+    #[builder(env = "APP_HOST")]
+    host: IpAddr,
+    #[builder(env = "APP_PORT", default = 8080)]
+    port: u16,
+    #[builder(env = "APP_WORKERS")]
+    workers: Option<usize>,
+    #[builder(env = "APP_TIMEOUT", setter(strip_option = false))]
+    timeout: Option<u64>,
+    tags: Vec<String>,
+}
+
+fn main() {
+    std::env::set_var("APP_HOST", "127.0.0.1");
+    std::env::set_var("APP_WORKERS", "4");
+    std::env::set_var("APP_TIMEOUT", "30");
+
+    let mut builder = ServiceBuilder::from_env().unwrap();
+    builder.name("api".to_owned());
+    builder.tags(vec!["web".to_owned()]);
+    let service = builder.build().unwrap();
+    assert_eq!(
+        service,
+        Service {
+            name: "api".to_owned(),
+            host: IpAddr::from([127, 0, 0, 1]),
+            port: 8080,
+            workers: Some(4),
+            timeout: Some(30),
+            tags: vec!["web".to_owned()],
+        }
+    );
+
+    // Values from the environment override what was set before.
+    std::env::set_var("APP_PORT", "9000");
+    let mut builder = Service::builder();
+    builder.port(1);
+    builder.fill_from_env().unwrap();
+    builder.name("api".to_owned());
+    builder.tags(Vec::new());
+    assert_eq!(builder.build().unwrap().port, 9000);
+
+    // Required fields missing from the environment are reported by build().
+    std::env::remove_var("APP_HOST");
+    let mut builder = ServiceBuilder::from_env().unwrap();
+    let err = builder.build().unwrap_err();
+    assert_eq!(err.to_string(), "missing required fields: name, host, tags");
+
+    std::env::set_var("APP_PORT", "eighty");
+    let err = ServiceBuilder::from_env().err().unwrap();
+    assert!(matches!(
+        err,
+        ServiceBuilderError::InvalidEnv {
+            var: "APP_PORT",
+            field: "port",
+            ..
+        }
+    ));
+    assert_eq!(
+        err.to_string(),
+        "invalid value of environment variable `APP_PORT` for field `port`: \
+         invalid digit found in string",
+    );
+    assert!(err.source().is_some());
+}
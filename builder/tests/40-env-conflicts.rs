@@ -0,0 +1,21 @@
+// An environment variable holds a single value, so `env` cannot be combined
+// with options building the field from several values. A typestate builder
+// cannot be filled from the environment either, because whether a field is
+// set has to be known at compile time.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(env = "APP_ARGS", each = "arg")]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Server {
+    #[builder(env = "APP_PORT")]
+    port: u16,
+}
+
+fn main() {}
@@ -0,0 +1,79 @@
+// The builder implements Default, creating the same empty builder as
+// Command::builder(), and Debug, which shows for each field whether it is
+//
+//   - `set`,
+//   - `pending`: required but not set yet, or
+//   - `default`: not set, and build() will use its default.
+//
+// The field values are not shown, so the field types need not implement
+// Debug. Deriving Debug with #[builder(derive(Debug))] replaces the generated
+// impl by one that shows the values.
+//
+// missing_fields() returns the names of the required fields that are not set
+// yet, which are the ones build() would report.
+//
+// A typestate builder only gets Default, for the builder with no fields set;
+// its type already tells which fields are set.
+
+use derive_builder::Builder;
+
+pub struct Handle;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    env: Vec<String>,
+    current_dir: Option<String>,
+    handle: Option<Handle>,
+}
+
+#[derive(Builder)]
+#[builder(derive(Debug))]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Builder, Debug)]
+#[builder(typestate)]
+pub struct Size {
+    width: u32,
+    height: u32,
+}
+
+fn main() {
+    let mut builder = CommandBuilder::default();
+    assert_eq!(builder.missing_fields(), ["executable", "env"]);
+    assert_eq!(
+        format!("{:?}", builder),
+        "CommandBuilder { executable: pending, args: default, env: pending, \
+         current_dir: default, handle: default }",
+    );
+
+    builder.executable("cargo".to_owned());
+    builder.arg("build".to_owned());
+    builder.handle(Handle);
+    assert_eq!(builder.missing_fields(), ["env"]);
+    assert_eq!(
+        format!("{:?}", builder),
+        "CommandBuilder { executable: set, args: set, env: pending, \
+         current_dir: default, handle: set }",
+    );
+
+    builder.env(vec![]);
+    assert!(builder.missing_fields().is_empty());
+    assert!(builder.build().is_ok());
+
+    let mut point = PointBuilder::default();
+    point.x(1);
+    assert_eq!(
+        format!("{:?}", point),
+        "PointBuilder { x: Some(1), y: None }"
+    );
+
+    let size = <SizeBuilder>::default().width(1).height(2).build();
+    assert_eq!(size.width, 1);
+    assert_eq!(size.height, 2);
+}
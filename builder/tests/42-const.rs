@@ -0,0 +1,70 @@
+// With #[builder(const)] the builder can be used in constant expressions:
+// builder(), the setters and build_const() are `const fn`, so a static
+// configuration can be written as
+//
+//     static CONFIG: Config = Config::builder().name("api").port(80).build_const();
+//
+// build_const() returns the value itself and panics if a required field is
+// missing, which fails the compilation when it is evaluated in a constant.
+// The runtime build() returning a Result is still available.
+//
+// Setters take and return the builder by value, so `const` implies the
+// "owned" pattern. Options whose setters call trait methods, like `each` or
+// `setter(into)`, are not supported. Defaults of unset fields have to be
+// constant expressions.
+//
+// Because a `const fn` cannot drop the value a setter replaces, setting a
+// field a second time leaks the previous value.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(const)]
+pub struct Config {
+    name: &'static str,
+    port: u16,
+    workers: Option<usize>,
+    #[builder(default = 3)]
+    retries: u32,
+    #[builder(skip = &[])]
+    aliases: &'static [&'static str],
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(const)]
+pub struct Range<T> {
+    start: T,
+    end: T,
+}
+
+static CONFIG: Config = Config::builder().name("api").port(80).build_const();
+
+const TABLE: [Range<u8>; 2] = [
+    Range::builder().start(0).end(9).build_const(),
+    Range::builder().start(10).end(99).build_const(),
+];
+
+fn main() {
+    assert_eq!(
+        CONFIG,
+        Config {
+            name: "api",
+            port: 80,
+            workers: None,
+            retries: 3,
+            aliases: &[],
+        }
+    );
+    assert_eq!(TABLE[1], Range { start: 10, end: 99 });
+
+    let config = Config::builder()
+        .name("worker")
+        .port(81)
+        .workers(4)
+        .retries(1)
+        .build();
+    assert_eq!(config.unwrap().workers, Some(4));
+
+    let err = Config::builder().name("api").build().unwrap_err();
+    assert_eq!(err.to_string(), "missing required fields: port");
+}
@@ -0,0 +1,17 @@
+// build_const() panics on a missing required field. Evaluated in a constant,
+// the panic is a compile error showing which field is missing.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(const)]
+pub struct Config {
+    name: &'static str,
+    port: u16,
+}
+
+static CONFIG: Config = Config::builder().name("api").build_const();
+
+fn main() {
+    let _ = &CONFIG;
+}
@@ -0,0 +1,32 @@
+// A const builder takes its values as they are: the setters cannot call
+// conversion or collection traits, and the builder is always passed by value.
+// Defaults have to be given as constant expressions, as `Default::default()`
+// cannot be called in a `const fn`.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(const, pattern = "mutable")]
+pub struct Config {
+    name: &'static str,
+}
+
+#[derive(Builder)]
+#[builder(const)]
+pub struct Command {
+    #[builder(setter(into))]
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(const)]
+pub struct Limits {
+    #[builder(default)]
+    memory: u64,
+    #[builder(skip)]
+    cache: Vec<u32>,
+}
+
+fn main() {}
@@ -0,0 +1,165 @@
+// Fields whose value is created by an async call, like a connection pool, can
+// be given #[builder(setter(async_fn))]. Their setter takes a future
+// resolving to a Result instead of the value:
+//
+//     fn pool<F, E>(&mut self, pool: F) -> &mut Self
+//     where
+//         F: Future<Output = Result<Pool, E>> + Send + 'static,
+//         E: Into<Box<dyn Error + Send + Sync>>;
+//
+// The future is only awaited by the builder's build_async(), which replaces
+// build(). It first reports the missing required fields like build() does,
+// then awaits the futures one after the other in field order. An error from a
+// future is returned as
+//
+//     ServiceBuilderError::InitFailed { field: "pool", source }
+//
+// with the original error as source, so it can be downcast to its type.
+//
+// The futures are moved out of the builder, so it cannot be cloned, which
+// rules out the "mutable" and "immutable" patterns.
+//
+// into_builder() and to_builder() store the field's value as a ready future,
+// so they are only available if the field type is Send + 'static. The value
+// itself need not be Send for the setter, only the future producing it.
+
+use derive_builder::Builder;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+#[derive(Debug, PartialEq)]
+pub struct Pool {
+    size: usize,
+}
+
+#[derive(Debug)]
+pub struct ConnectError(&'static str);
+
+impl Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot connect to {}", self.0)
+    }
+}
+
+impl Error for ConnectError {}
+
+async fn connect(url: &'static str, size: usize) -> Result<Pool, ConnectError> {
+    if url.starts_with("postgres://") {
+        Ok(Pool { size })
+    } else {
+        Err(ConnectError(url))
+    }
+}
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Service {
+    name: String,
+    #[builder(setter(async_fn))]
+    pool: Pool,
+    #[builder(setter(async_fn))]
+    cache: Option<Pool>,
+    #[builder(setter(async_fn), default = Pool { size: 1 })]
+    replica: Pool,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(pattern = "owned")]
+pub struct Worker {
+    #[builder(setter(async_fn))]
+    threads: usize,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Svc<P> {
+    #[builder(setter(async_fn))]
+    pool: P,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Local {
+    #[builder(setter(async_fn))]
+    counter: Rc<u32>,
+}
+
+fn main() {
+    let mut builder = Service::builder();
+    builder.name("api".to_owned());
+    builder.pool(connect("postgres://db", 8));
+    builder.cache(async { Ok::<_, ConnectError>(Pool { size: 2 }) });
+    let service = block_on(builder.build_async()).unwrap();
+    assert_eq!(
+        service,
+        Service {
+            name: "api".to_owned(),
+            pool: Pool { size: 8 },
+            cache: Some(Pool { size: 2 }),
+            replica: Pool { size: 1 },
+        }
+    );
+
+    let mut builder = Service::builder();
+    assert_eq!(builder.missing_fields(), ["name", "pool"]);
+    builder.name("api".to_owned());
+    builder.pool(connect("mysql://db", 8));
+    let err = block_on(builder.build_async()).unwrap_err();
+    assert!(matches!(
+        err,
+        ServiceBuilderError::InitFailed { field: "pool", .. }
+    ));
+    assert_eq!(
+        err.to_string(),
+        "initializing field `pool` failed: cannot connect to mysql://db",
+    );
+    let source = err.source().unwrap();
+    assert!(source.downcast_ref::<ConnectError>().is_some());
+
+    let err = block_on(Service::builder().build_async()).unwrap_err();
+    assert_eq!(err.to_string(), "missing required fields: name, pool");
+
+    // The future can also fail with a boxed error or a string.
+    let worker = Worker::builder().threads(async { Ok::<_, String>(4) });
+    assert_eq!(
+        block_on(worker.build_async()).unwrap(),
+        Worker { threads: 4 }
+    );
+    let worker = Worker::builder().threads(async { Err::<usize, _>("no threads") });
+    let err = block_on(worker.build_async()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "initializing field `threads` failed: no threads"
+    );
+
+    let svc = Svc::builder().pool(async { Ok::<_, String>(Pool { size: 3 }) });
+    let svc = block_on(svc.build_async()).unwrap();
+    let svc = block_on(svc.into_builder().build_async()).unwrap();
+    assert_eq!(svc.pool, Pool { size: 3 });
+
+    let local = Local::builder().counter(async { Ok::<_, String>(Rc::new(5)) });
+    let local = block_on(local.build_async()).unwrap();
+    assert_eq!(*local.counter, 5);
+}
+
+/// Runs a future to completion on the current thread.
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    let waker = Waker::from(Arc::new(Noop));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
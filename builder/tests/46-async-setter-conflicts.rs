@@ -0,0 +1,19 @@
+// A future can only be awaited once, so a `setter(async_fn)` field cannot be
+// part of a builder that is cloned, and its setter cannot convert its input.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(pattern = "mutable")]
+pub struct Service {
+    #[builder(setter(async_fn))]
+    pool: u32,
+}
+
+#[derive(Builder)]
+pub struct Worker {
+    #[builder(setter(async_fn, into))]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+// A one-at-a-time setter needs a field that can be extended. Without type
+// arguments the macro cannot tell a collection from a scalar, so apart from
+// the string types such fields are rejected rather than silently turned into
+// optional fields with a setter that cannot be called.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Counter {
+    #[builder(each = "tick")]
+    ticks: u32,
+    #[builder(each = "flag")]
+    flags: Flags,
+}
+
+#[derive(Default)]
+pub struct Flags(u8);
+
+fn main() {}
@@ -0,0 +1,42 @@
+// The functions returning a builder, builder() and the round trip methods
+// into_builder() and to_builder(), have the builder's visibility. With a
+// private builder they can only be called inside the module, where the
+// builder type can be named.
+
+mod config {
+    use derive_builder::Builder;
+
+    #[derive(Builder, Clone)]
+    #[builder(vis = "")]
+    pub struct Config {
+        pub name: String,
+    }
+
+    #[derive(Builder)]
+    #[builder(typestate, vis = "")]
+    pub struct Point {
+        pub x: i32,
+    }
+
+    pub fn rename(config: &Config, name: &str) -> Config {
+        let mut builder: ConfigBuilder = config.to_builder();
+        builder.name(name.to_owned());
+        builder.build().unwrap()
+    }
+
+    pub fn origin() -> Point {
+        Point::builder().x(0).build()
+    }
+}
+
+fn main() {
+    let config = config::Config::builder();
+    let point = config::Point::builder();
+    let _ = config::origin();
+    let server = config::Config {
+        name: "server".to_owned(),
+    };
+    let _ = config::rename(&server, "proxy");
+    let _ = server.to_builder();
+    let _ = server.into_builder();
+}
@@ -7,7 +7,46 @@ fn tests() {
     t.pass("tests/04-call-build.rs");
     t.pass("tests/05-method-chaining.rs");
     t.pass("tests/06-optional-field.rs");
-    //t.pass("tests/07-repeated-field.rs");
-    //t.compile_fail("tests/08-unrecognized-attribute.rs");
-    //t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/07-repeated-field.rs");
+    t.compile_fail("tests/08-unrecognized-attribute.rs");
+    t.pass("tests/09-redefined-prelude-types.rs");
+    t.compile_fail("tests/10-multiple-errors.rs");
+    t.pass("tests/11-redefined-prelude-values.rs");
+    t.pass("tests/12-generics.rs");
+    t.pass("tests/13-error-type.rs");
+    t.pass("tests/14-typestate.rs");
+    t.compile_fail("tests/15-typestate-missing-field.rs");
+    t.pass("tests/16-default.rs");
+    t.compile_fail("tests/17-default-mismatch.rs");
+    t.pass("tests/18-into-setters.rs");
+    t.pass("tests/19-patterns.rs");
+    t.compile_fail("tests/20-unknown-pattern.rs");
+    t.pass("tests/21-validate.rs");
+    t.pass("tests/22-tuple-struct.rs");
+    t.pass("tests/23-enum.rs");
+    t.compile_fail("tests/24-enum-variant-attribute.rs");
+    t.pass("tests/25-to-builder.rs");
+    t.pass("tests/26-sub-builder.rs");
+    t.compile_fail("tests/27-sub-builder-conflicts.rs");
+    t.pass("tests/28-each-collections.rs");
+    t.pass("tests/29-option-paths.rs");
+    t.pass("tests/30-builder-visibility.rs");
+    t.compile_fail("tests/31-private-setter.rs");
+    t.pass("tests/32-skip.rs");
+    t.compile_fail("tests/33-skip-conflict.rs");
+    t.pass("tests/34-docs.rs");
+    t.pass("tests/35-no-std.rs");
+    t.pass("tests/36-setter-names.rs");
+    t.compile_fail("tests/37-setter-transform-errors.rs");
+    t.pass("tests/38-serde.rs");
+    t.pass("tests/39-env.rs");
+    t.compile_fail("tests/40-env-conflicts.rs");
+    t.pass("tests/41-builder-traits.rs");
+    t.pass("tests/42-const.rs");
+    t.compile_fail("tests/43-const-missing-field.rs");
+    t.compile_fail("tests/44-const-conflicts.rs");
+    t.pass("tests/45-async-setter.rs");
+    t.compile_fail("tests/46-async-setter-conflicts.rs");
+    t.compile_fail("tests/47-each-non-collection.rs");
+    t.compile_fail("tests/48-private-builder.rs");
 }